@@ -0,0 +1,290 @@
+use credentials;
+use error::Error;
+use error::Result;
+use game_status::PlayerName;
+use hearts_client::ClientSummary;
+use hearts_client::HeartsClient;
+use hearts_client::Password;
+use retry::RetryPolicy;
+use strategy;
+use strategy::Registry;
+
+use libc;
+
+use serde_json;
+use serde_json::Value;
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::thread;
+
+/// Set once the process is interrupted, after which the clients stop between games.
+pub static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Several accounts played at once from one process, each by its own client on its own
+/// thread. Log lines are prefixed with the account's player name, and each account logs its
+/// games to its own directory under the log directory.
+///
+/// Accounts are read from a JSON or TOML file:
+///
+/// ```text
+/// [[accounts]]
+/// user = "Derek Williams"
+/// password = "secret"
+/// server = "127.0.0.1:8080"
+/// strategy = "monte-carlo"
+/// strategy_config = { samples = 100 }
+/// repeat = true
+/// ```
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Accounts {
+    accounts: Vec<Account>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Account {
+    user: String,
+    password: Password,
+    server: String,
+    #[serde(default = "default_strategy")]
+    strategy: String,
+    #[serde(default)]
+    strategy_config: Value,
+    #[serde(default)]
+    repeat: bool,
+}
+
+/// How each account's client did, in the order the accounts were listed.
+pub struct Summary {
+    results: Vec<(String, Option<ClientSummary>)>,
+}
+
+fn default_strategy() -> String {
+    "defensive".to_owned()
+}
+
+impl Accounts {
+    /// The file holds passwords, so only its owner may read or write it.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Accounts> {
+        credentials::check_permissions(path.as_ref())?;
+        let accounts: Accounts = serde_json::from_value(strategy::read_parameters(path)?)?;
+        if accounts.accounts.is_empty() {
+            return Err(Error::Game("No accounts are listed".to_owned()));
+        }
+        let mut users = BTreeSet::new();
+        let registry = Registry::standard();
+        for account in &accounts.accounts {
+            if !users.insert(&account.user) {
+                return Err(Error::Game(format!("{} is listed more than once", account.user)));
+            }
+            let player_name = PlayerName::new(account.user.as_str());
+            registry.create(&account.strategy, player_name, &account.strategy_config)?;
+        }
+        Ok(accounts)
+    }
+
+    /// Plays every account until all of their clients stop, archiving their games if asked.
+    /// Once interrupted, each client stops when it is between games, and a second interrupt
+    /// stops the process at once.
+    pub fn play<P: AsRef<Path>>(
+        self,
+        log_dir: P,
+        retry_policy: RetryPolicy,
+        archive: bool,
+    ) -> Summary {
+        let log_dir = log_dir.as_ref();
+        stop_on_interrupt();
+        let mut handles = Vec::new();
+        for account in self.accounts {
+            let user = account.user.clone();
+            let log_dir = log_dir.join(&account.user);
+            let retry_policy = retry_policy.clone();
+            let handle = thread::Builder::new()
+                .name(user.clone())
+                .spawn(move || account.play(log_dir, retry_policy, archive));
+            handles.push((user, handle));
+        }
+
+        let results = handles
+            .into_iter()
+            .map(|(user, handle)| {
+                let summary = match handle {
+                    Ok(handle) => handle.join().ok(),
+                    Err(e) => {
+                        error!("Could not start playing for {}: {}", user, e);
+                        None
+                    }
+                };
+                (user, summary)
+            })
+            .collect();
+        Summary { results: results }
+    }
+}
+
+impl Account {
+    fn play(self, log_dir: PathBuf, retry_policy: RetryPolicy, archive: bool) -> ClientSummary {
+        let player_name = PlayerName::new(self.user);
+        let strategy = Registry::standard()
+            .create(&self.strategy, player_name, &self.strategy_config)
+            .unwrap();
+        info!("Playing {} on {}", self.strategy, self.server);
+        HeartsClient::new(
+            self.password,
+            &self.server,
+            strategy,
+            retry_policy,
+            self.repeat,
+        ).log_to(log_dir)
+            .archive(archive)
+            .stop_on(&INTERRUPTED)
+            .play()
+    }
+}
+
+/// Sets `INTERRUPTED` on the first interrupt instead of stopping the process, which the next
+/// one does.
+#[cfg(unix)]
+pub fn stop_on_interrupt() {
+    extern "C" fn interrupted(_: libc::c_int) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        unsafe { libc::signal(libc::SIGINT, libc::SIG_DFL) };
+    }
+    let handler: extern "C" fn(libc::c_int) = interrupted;
+    unsafe { libc::signal(libc::SIGINT, handler as libc::sighandler_t) };
+}
+
+#[cfg(not(unix))]
+pub fn stop_on_interrupt() {}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.results
+            .iter()
+            .map(|&(ref user, _)| user.chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(7);
+        writeln!(
+            f,
+            "{: <w$} {: >6} {: >10} {: >10} {: >7} {: >9} {: >7}",
+            "Account",
+            "Games",
+            "Points",
+            "Per game",
+            "Wins",
+            "Cancelled",
+            "Errors",
+            w = width
+        )?;
+        for &(ref user, ref summary) in &self.results {
+            let summary = match *summary {
+                Some(ref summary) => summary,
+                None => {
+                    writeln!(f, "{: <w$} stopped unexpectedly", user, w = width)?;
+                    continue;
+                }
+            };
+            let games = summary.finished.len();
+            let points = summary.finished.iter().map(|game| game.score).sum::<i32>();
+            let wins = summary.finished.iter().filter(|game| game.place == 1).count();
+            writeln!(
+                f,
+                "{: <w$} {: >6} {: >10} {: >10.3} {: >7} {: >9} {: >7}",
+                user,
+                games,
+                points,
+                points as f64 / games.max(1) as f64,
+                wins,
+                summary.cancelled,
+                summary.errors,
+                w = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hearts_client::FinishedGame;
+
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    /// A copy of the sample accounts file with the given permissions.
+    fn copy_sample(extension: &str, mode: u32) -> PathBuf {
+        let file_name = format!("hearts-accounts-{}-{:o}.{}", process::id(), mode, extension);
+        let path = env::temp_dir().join(file_name);
+        fs::copy(format!("samples/accounts.{}", extension), &path).unwrap();
+        set_mode(&path, mode);
+        path
+    }
+
+    #[cfg(unix)]
+    fn set_mode(path: &Path, mode: u32) {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[cfg(not(unix))]
+    fn set_mode(_: &Path, _: u32) {}
+
+    #[test]
+    fn reads_accounts() {
+        for extension in &["json", "toml"] {
+            let path = copy_sample(extension, 0o600);
+            let accounts = Accounts::load(&path).unwrap();
+            fs::remove_file(path).unwrap();
+            assert_eq!(2, accounts.accounts.len());
+            assert_eq!("defensive", accounts.accounts[0].strategy);
+            assert_eq!(Value::Null, accounts.accounts[0].strategy_config);
+            assert_eq!(100, accounts.accounts[1].strategy_config["samples"]);
+            assert!(accounts.accounts[1].repeat);
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn rejects_shared_files() {
+        let path = copy_sample("toml", 0o644);
+        let loaded = Accounts::load(&path);
+        fs::remove_file(path).unwrap();
+        assert!(loaded.is_err());
+    }
+
+    #[test]
+    fn summarizes_accounts() {
+        let finished = |score, place| {
+            FinishedGame {
+                score: score,
+                place: place,
+            }
+        };
+        let summary = Summary {
+            results: vec![
+                (
+                    "Derek Williams".to_owned(),
+                    Some(ClientSummary {
+                        finished: vec![finished(20, 1), finished(45, 3)],
+                        cancelled: 1,
+                        errors: 0,
+                    })
+                ),
+                ("Bill".to_owned(), None),
+            ],
+        };
+        let output = format!("{}", summary);
+
+        assert!(output.contains("Derek Williams      2         65     32.500       1         1"));
+        assert!(output.contains("Bill           stopped unexpectedly"));
+    }
+}
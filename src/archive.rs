@@ -0,0 +1,372 @@
+use error::Error;
+use error::Result;
+use game_status::GameInstanceState;
+use game_status::GameStatus;
+use game_status::PlayerName;
+use game_status::dto::GameStatusDto;
+
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+use serde_json;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Ending of the archive of a game, `<game_id>.jsonl.gz`.
+pub const EXTENSION: &'static str = "jsonl.gz";
+/// Ending of the archive of the decisions taken in a game, `<game_id>.decisions.jsonl.gz`.
+pub const DECISIONS_EXTENSION: &'static str = "decisions.jsonl.gz";
+/// The index of the games in an archive directory.
+pub const INDEX: &'static str = "index.jsonl";
+
+/// The snapshots of a logged game, by round id and deal number. Deal 0 is the passing phase
+/// and deal 14 the end of the round.
+pub type Snapshots = BTreeMap<(u32, u32), GameStatus>;
+
+/// Logged games kept in one compressed file each, rather than a directory of snapshots, with
+/// an index of the games. Each snapshot is a line of JSON, `{"round_id": 1, "deal_number": 3,
+/// "status": ...}` with the status as the server sent it, compressed as a gzip member of its
+/// own so snapshots are appended without rewriting the file.
+pub struct Archive {
+    dir: PathBuf,
+}
+
+/// A game in the index, with the participants' scores as last logged.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct IndexEntry {
+    pub game_id: String,
+    pub rounds: u32,
+    pub cancelled: bool,
+    pub participants: Vec<Participant>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+pub struct Participant {
+    pub player_name: PlayerName,
+    pub score: i32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    round_id: u32,
+    deal_number: u32,
+    status: GameStatusDto,
+}
+
+impl IndexEntry {
+    pub fn new(game_status: &GameStatus) -> IndexEntry {
+        IndexEntry {
+            game_id: game_status.current_game_id.clone(),
+            rounds: game_status.current_round_id,
+            cancelled: game_status.current_game_state == GameInstanceState::Cancelled,
+            participants: game_status
+                .game_players
+                .iter()
+                .map(|player| {
+                    Participant {
+                        player_name: player.team_name.clone(),
+                        score: player.current_score,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Archive {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Archive {
+        Archive { dir: dir.into() }
+    }
+
+    pub fn append(&self, game_status: &GameStatus, deal_number: u32) -> Result<()> {
+        let snapshot = Snapshot {
+            round_id: game_status.current_round_id,
+            deal_number: deal_number,
+            status: GameStatusDto::from(game_status),
+        };
+        let mut line = serde_json::to_string(&snapshot)?;
+        line.push('\n');
+        self.append_compressed(&game_file(&self.dir, &game_status.current_game_id), &line)
+    }
+
+    /// Appends a line of the client's decision log to the game's decisions, which are kept
+    /// beside its snapshots rather than in one file for every game.
+    pub fn append_decision(&self, game_id: &str, line: &str) -> Result<()> {
+        self.append_compressed(&decisions_file(&self.dir, game_id), line)
+    }
+
+    /// Adds the game to the index, replacing any entry it already has.
+    pub fn index_game(&self, game_status: &GameStatus) -> Result<()> {
+        self.add_to_index(&IndexEntry::new(game_status))
+    }
+
+    /// The games in the index, in the order they were first added.
+    pub fn index(&self) -> Result<Vec<IndexEntry>> {
+        let path = self.dir.join(INDEX);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let mut entries: Vec<IndexEntry> = Vec::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let entry: IndexEntry = serde_json::from_str(&line?)?;
+            match entries.iter().position(|other| other.game_id == entry.game_id) {
+                Some(index) => entries[index] = entry,
+                None => entries.push(entry),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Archives and indexes every game logged as a directory of snapshots under `game_log`,
+    /// replacing any archive of the same game. The directories are left as they are.
+    pub fn convert<P: AsRef<Path>>(&self, game_log: P) -> Result<Vec<IndexEntry>> {
+        let mut game_dirs = Vec::new();
+        for entry in fs::read_dir(game_log)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                game_dirs.push(path);
+            }
+        }
+        game_dirs.sort();
+
+        let mut converted = Vec::new();
+        for game_dir in game_dirs {
+            let snapshots = read_game_dir(&game_dir)?;
+            let entry = match snapshots.values().next_back() {
+                Some(last) => IndexEntry::new(last),
+                None => continue,
+            };
+            let mut lines = String::new();
+            for (&(round_id, deal_number), game_status) in &snapshots {
+                let snapshot = Snapshot {
+                    round_id: round_id,
+                    deal_number: deal_number,
+                    status: GameStatusDto::from(game_status),
+                };
+                lines.push_str(&serde_json::to_string(&snapshot)?);
+                lines.push('\n');
+            }
+            fs::DirBuilder::new().recursive(true).create(&self.dir)?;
+            write_compressed(&game_file(&self.dir, &entry.game_id), lines.as_bytes())?;
+            let decisions = game_dir.join("decisions.jsonl");
+            if decisions.exists() {
+                let mut lines = Vec::new();
+                File::open(decisions)?.read_to_end(&mut lines)?;
+                write_compressed(&decisions_file(&self.dir, &entry.game_id), &lines)?;
+            }
+            self.add_to_index(&entry)?;
+            converted.push(entry);
+        }
+        Ok(converted)
+    }
+
+    fn add_to_index(&self, entry: &IndexEntry) -> Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        self.open_appending(&self.dir.join(INDEX))?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Appends the line as a gzip member of its own.
+    fn append_compressed(&self, path: &Path, line: &str) -> Result<()> {
+        let mut encoder = GzEncoder::new(self.open_appending(path)?, Compression::default());
+        encoder.write_all(line.as_bytes())?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    fn open_appending(&self, path: &Path) -> Result<File> {
+        fs::DirBuilder::new().recursive(true).create(&self.dir)?;
+        Ok(OpenOptions::new().create(true).append(true).open(path)?)
+    }
+}
+
+/// Reads a logged game from its archive in the log directory or, if it was not archived, its
+/// directory of snapshots.
+pub fn read_game<P: AsRef<Path>>(log_dir: P, game_id: &str) -> Result<Snapshots> {
+    let log_dir = log_dir.as_ref();
+    let archive = game_file(log_dir, game_id);
+    let snapshots = if archive.exists() {
+        read_archive(&archive)?
+    } else {
+        read_game_dir(&log_dir.join(game_id))?
+    };
+    if snapshots.is_empty() {
+        return Err(Error::Game(
+            format!("No snapshots of {} in {}", game_id, log_dir.display()),
+        ));
+    }
+    Ok(snapshots)
+}
+
+/// The ids of the games logged in the directory, archived or not.
+pub fn game_ids<P: AsRef<Path>>(log_dir: P) -> Result<Vec<String>> {
+    let suffix = format!(".{}", EXTENSION);
+    let decisions_suffix = format!(".{}", DECISIONS_EXTENSION);
+    let mut game_ids = BTreeSet::new();
+    for entry in fs::read_dir(log_dir)? {
+        let path = entry?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        if path.is_dir() {
+            game_ids.insert(name);
+        } else if name.ends_with(&suffix) && !name.ends_with(&decisions_suffix) {
+            game_ids.insert(name[..name.len() - suffix.len()].to_owned());
+        }
+    }
+    Ok(game_ids.into_iter().collect())
+}
+
+fn read_archive(path: &Path) -> Result<Snapshots> {
+    let mut snapshots = BTreeMap::new();
+    let decoder = BufReader::new(MultiGzDecoder::new(File::open(path)?));
+    for line in decoder.lines() {
+        let snapshot: Snapshot = serde_json::from_str(&line?)?;
+        snapshots.insert(
+            (snapshot.round_id, snapshot.deal_number),
+            GameStatus::try_from(snapshot.status)?,
+        );
+    }
+    Ok(snapshots)
+}
+
+/// Reads the `<round>-<deal>.json` snapshots the client logs to a game's directory.
+fn read_game_dir(game_dir: &Path) -> Result<Snapshots> {
+    let mut snapshots = BTreeMap::new();
+    for entry in fs::read_dir(game_dir)? {
+        let path = entry?.path();
+        let numbers = path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| snapshot_numbers(stem));
+        if let Some(numbers) = numbers {
+            let mut string = String::new();
+            File::open(&path)?.read_to_string(&mut string)?;
+            let dto: GameStatusDto = serde_json::from_str(&string)?;
+            snapshots.insert(numbers, GameStatus::try_from(dto)?);
+        }
+    }
+    Ok(snapshots)
+}
+
+fn write_compressed(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut encoder = GzEncoder::new(File::create(path)?, Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+fn game_file(dir: &Path, game_id: &str) -> PathBuf {
+    dir.join(format!("{}.{}", game_id, EXTENSION))
+}
+
+fn decisions_file(dir: &Path, game_id: &str) -> PathBuf {
+    dir.join(format!("{}.{}", game_id, DECISIONS_EXTENSION))
+}
+
+/// Round and deal numbers from a snapshot file name such as `01-13`.
+fn snapshot_numbers(stem: &str) -> Option<(u32, u32)> {
+    let mut numbers = stem.splitn(2, '-').map(|number| number.parse::<u32>());
+    match (numbers.next(), numbers.next()) {
+        (Some(Ok(round_id)), Some(Ok(deal_number))) => Some((round_id, deal_number)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine;
+
+    use std::env;
+    use std::process;
+
+    fn read_decisions(dir: &Path, game_id: &str) -> Vec<String> {
+        let file = File::open(decisions_file(dir, game_id)).unwrap();
+        BufReader::new(MultiGzDecoder::new(file))
+            .lines()
+            .map(|line| line.unwrap())
+            .collect()
+    }
+
+    fn game_status(game_id: &str) -> GameStatus {
+        let round = engine::test_round(engine::round_parameters(1), 5);
+        round.game_status(game_id, GameInstanceState::Running, &engine::test_players()[0])
+    }
+
+    #[test]
+    fn appends_snapshots_and_indexes_games() {
+        let dir = env::temp_dir().join(format!("hearts-archive-test-{}", process::id()));
+        let archive = Archive::new(dir.clone());
+        let mut status = game_status("appended");
+        archive.append(&status, 0).unwrap();
+        archive.append(&status, 1).unwrap();
+        archive.append_decision("appended", "{\"action\": \"pass\"}\n").unwrap();
+        archive.append_decision("appended", "{\"action\": \"play\"}\n").unwrap();
+        archive.index_game(&status).unwrap();
+        status.game_players[0].current_score = 26;
+        archive.index_game(&status).unwrap();
+
+        let snapshots = read_game(&dir, "appended").unwrap();
+        let decisions = read_decisions(&dir, "appended");
+        let index = archive.index().unwrap();
+        let game_ids = game_ids(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(vec![(1, 0), (1, 1)], snapshots.keys().cloned().collect::<Vec<_>>());
+        assert_eq!(status.my_current_hand, snapshots[&(1, 1)].my_current_hand);
+        assert_eq!(vec!["{\"action\": \"pass\"}", "{\"action\": \"play\"}"], decisions);
+        assert_eq!(1, index.len());
+        assert_eq!(26, index[0].participants[0].score);
+        assert_eq!(4, index[0].participants.len());
+        assert_eq!(vec!["appended".to_owned()], game_ids);
+    }
+
+    #[test]
+    fn converts_game_directories() {
+        let game_log = env::temp_dir().join(format!("hearts-convert-test-{}", process::id()));
+        let game_dir = game_log.join("converted");
+        fs::create_dir_all(&game_dir).unwrap();
+        let dto = GameStatusDto::from(&game_status("converted"));
+        File::create(game_dir.join("01-00.json"))
+            .unwrap()
+            .write_all(serde_json::to_string(&dto).unwrap().as_bytes())
+            .unwrap();
+        File::create(game_dir.join("decisions.jsonl"))
+            .unwrap()
+            .write_all(b"{\"action\": \"pass\"}\n")
+            .unwrap();
+
+        let archive = Archive::new(game_log.clone());
+        let converted = archive.convert(&game_log).unwrap();
+        fs::remove_dir_all(&game_dir).unwrap();
+        let snapshots = read_game(&game_log, "converted").unwrap();
+        let decisions = read_decisions(&game_log, "converted");
+        fs::remove_dir_all(&game_log).unwrap();
+
+        assert_eq!(1, converted.len());
+        assert_eq!("converted", converted[0].game_id);
+        assert_eq!(vec![(1, 0)], snapshots.keys().cloned().collect::<Vec<_>>());
+        assert_eq!(vec!["{\"action\": \"pass\"}"], decisions);
+    }
+
+    #[test]
+    fn snapshot_names() {
+        assert_eq!(Some((1, 13)), snapshot_numbers("01-13"));
+        assert_eq!(None, snapshot_numbers("notes"));
+    }
+}
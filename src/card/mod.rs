@@ -1,12 +1,17 @@
 pub mod dto;
 mod suit;
 mod rank;
+mod set;
 
 pub use card::suit::Suit;
 pub use card::rank::Rank;
+pub use card::set::CardSet;
+
+use error::Error;
+use error::Result;
 
 use std::fmt;
-use std::collections::BTreeSet;
+use std::str::FromStr;
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord, Clone, Copy)]
 pub struct Card {
@@ -22,14 +27,15 @@ impl Card {
         }
     }
 
-    pub fn all() -> BTreeSet<Card> {
-        let mut cards = BTreeSet::new();
-        for suit in Suit::all() {
-            for rank in Rank::all() {
-                cards.insert(rank.of(suit));
-            }
-        }
-        cards
+    pub fn all() -> CardSet {
+        CardSet::all()
+    }
+
+    /// The card written rank first with a suit letter, as in "QS" or "TH", the way scenarios
+    /// are written by hand.
+    pub fn code(&self) -> String {
+        let suit: &str = self.suit.into();
+        format!("{}{}", self.rank, &suit[..1])
     }
 }
 
@@ -39,6 +45,38 @@ impl fmt::Display for Card {
     }
 }
 
+/// Reads cards written rank first with a suit letter, as in "QS", "10H" or "TH", or the way
+/// they are displayed, as in "\u{2660}Q".
+impl FromStr for Card {
+    type Err = Error;
+
+    fn from_str(string: &str) -> Result<Card> {
+        let string = string.trim();
+        let first = string.chars().next();
+        let last = string.chars().next_back();
+        let (suit, rank) = match (first.and_then(suit), last.and_then(suit)) {
+            (Some(suit), _) => (suit, &string[first.unwrap().len_utf8()..]),
+            (None, Some(suit)) => (suit, &string[..string.len() - last.unwrap().len_utf8()]),
+            (None, None) => return Err(Error::parsing("Card", string)),
+        };
+        let rank = match &*rank.to_uppercase() {
+            "T" => Rank::Ten,
+            rank => Rank::from_str(rank).map_err(|_| Error::parsing("Card", string))?,
+        };
+        Ok(rank.of(suit))
+    }
+}
+
+fn suit(c: char) -> Option<Suit> {
+    match c {
+        'S' | 's' | '\u{2660}' => Some(Suit::Spade),
+        'H' | 'h' | '\u{2665}' => Some(Suit::Heart),
+        'D' | 'd' | '\u{2666}' => Some(Suit::Diamond),
+        'C' | 'c' | '\u{2663}' => Some(Suit::Club),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -61,6 +99,29 @@ mod tests {
         assert_eq!(Rank::Ace, Rank::from_str(Rank::Ace.into()).unwrap());
     }
 
+    #[test]
+    fn parse_cards() {
+        let queen = Rank::Queen.of(Suit::Spade);
+        let ten = Rank::Ten.of(Suit::Heart);
+        assert_eq!(queen, Card::from_str("QS").unwrap());
+        assert_eq!(queen, Card::from_str("\u{2660}Q").unwrap());
+        assert_eq!(ten, Card::from_str("10H").unwrap());
+        assert_eq!(ten, Card::from_str("TH").unwrap());
+        assert_eq!(ten, Card::from_str("\u{2665}10").unwrap());
+        assert_eq!(Rank::Two.of(Suit::Club), Card::from_str("2c").unwrap());
+        for card in Card::all() {
+            assert_eq!(card, Card::from_str(&format!("{}", card)).unwrap());
+            assert_eq!(card, Card::from_str(&card.code()).unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_cards() {
+        for string in &["", "Q", "S", "1S", "QX", "\u{2660}", "QSS"] {
+            assert!(Card::from_str(string).is_err(), "{} parsed", string);
+        }
+    }
+
     #[test]
     fn invalid_rank() {
         assert_eq!(
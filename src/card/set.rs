@@ -0,0 +1,267 @@
+use card::Card;
+use card::Rank;
+use card::Suit;
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::iter::FromIterator;
+
+const SUITS: [Suit; 4] = [Suit::Spade, Suit::Heart, Suit::Diamond, Suit::Club];
+const RANKS: [Rank; 13] = [
+    Rank::Two,
+    Rank::Three,
+    Rank::Four,
+    Rank::Five,
+    Rank::Six,
+    Rank::Seven,
+    Rank::Eight,
+    Rank::Nine,
+    Rank::Ten,
+    Rank::Jack,
+    Rank::Queen,
+    Rank::King,
+    Rank::Ace,
+];
+const ALL: u64 = (1 << 52) - 1;
+const SUIT: u64 = (1 << 13) - 1;
+
+/// A set of cards kept as one bit per card, in the same order as cards sort: by suit, then
+/// by rank within the suit. Iterating goes from the lowest card to the highest.
+#[derive(PartialEq, Eq, Hash, Default, Clone, Copy)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub fn new() -> CardSet {
+        CardSet(0)
+    }
+
+    pub fn all() -> CardSet {
+        CardSet(ALL)
+    }
+
+    /// The cards with the given bits set, as returned by `bits`.
+    pub fn from_bits(bits: u64) -> CardSet {
+        CardSet(bits & ALL)
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// The bit a card is kept in.
+    pub fn bit(card: &Card) -> u64 {
+        1 << (card.suit as u32 * 13 + card.rank as u32)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, card: &Card) -> bool {
+        self.0 & Self::bit(card) != 0
+    }
+
+    /// Adds the card, returning whether it was not in the set already.
+    pub fn insert(&mut self, card: Card) -> bool {
+        let absent = !self.contains(&card);
+        self.0 |= Self::bit(&card);
+        absent
+    }
+
+    /// Takes the card out, returning whether it was in the set.
+    pub fn remove(&mut self, card: &Card) -> bool {
+        let present = self.contains(card);
+        self.0 &= !Self::bit(card);
+        present
+    }
+
+    pub fn union(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    pub fn difference(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    pub fn is_subset(&self, other: CardSet) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    /// The cards of the given suit.
+    pub fn suit(&self, suit: Suit) -> CardSet {
+        CardSet(self.0 & (SUIT << (suit as u32 * 13)))
+    }
+
+    pub fn iter(&self) -> Iter {
+        Iter(self.0)
+    }
+}
+
+/// Cards from the lowest to the highest, or the other way round when reversed.
+pub struct Iter(u64);
+
+fn card(index: u32) -> Card {
+    RANKS[(index % 13) as usize].of(SUITS[(index / 13) as usize])
+}
+
+impl Iterator for Iter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = self.0.trailing_zeros();
+        self.0 &= self.0 - 1;
+        Some(card(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+        let index = 63 - self.0.leading_zeros();
+        self.0 &= !(1 << index);
+        Some(card(index))
+    }
+}
+
+impl ExactSizeIterator for Iter {}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a CardSet {
+    type Item = Card;
+    type IntoIter = Iter;
+
+    fn into_iter(self) -> Iter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(cards: I) -> CardSet {
+        let mut set = CardSet::new();
+        set.extend(cards);
+        set
+    }
+}
+
+impl<'a> FromIterator<&'a Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = &'a Card>>(cards: I) -> CardSet {
+        cards.into_iter().cloned().collect()
+    }
+}
+
+impl Extend<Card> for CardSet {
+    fn extend<I: IntoIterator<Item = Card>>(&mut self, cards: I) {
+        for card in cards {
+            self.insert(card);
+        }
+    }
+}
+
+impl<'a> From<&'a BTreeSet<Card>> for CardSet {
+    fn from(cards: &'a BTreeSet<Card>) -> CardSet {
+        cards.iter().collect()
+    }
+}
+
+impl From<CardSet> for BTreeSet<Card> {
+    fn from(cards: CardSet) -> BTreeSet<Card> {
+        cards.iter().collect()
+    }
+}
+
+impl fmt::Debug for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// Cards separated by spaces, lowest first.
+impl fmt::Display for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cards = self.iter().map(|card| format!("{}", card)).collect::<Vec<_>>();
+        write!(f, "{}", cards.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::Rank::*;
+    use card::Suit::*;
+
+    #[test]
+    fn same_order_as_cards() {
+        let cards = CardSet::all().iter().collect::<Vec<_>>();
+        let sorted = Suit::all()
+            .into_iter()
+            .flat_map(|suit| Rank::all().into_iter().map(move |rank| rank.of(suit)))
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect::<Vec<_>>();
+        assert_eq!(52, cards.len());
+        assert_eq!(sorted, cards);
+
+        let reversed = CardSet::all().iter().rev().collect::<Vec<_>>();
+        assert_eq!(sorted.into_iter().rev().collect::<Vec<_>>(), reversed);
+    }
+
+    #[test]
+    fn set_operations() {
+        let mut hand = vec![Queen.of(Spade), Two.of(Club), Ten.of(Heart)]
+            .into_iter()
+            .collect::<CardSet>();
+        assert_eq!(3, hand.len());
+        assert!(hand.contains(&Queen.of(Spade)));
+        assert!(!hand.insert(Queen.of(Spade)));
+        assert!(hand.insert(Ace.of(Club)));
+        assert!(hand.remove(&Two.of(Club)));
+        assert!(!hand.remove(&Two.of(Club)));
+
+        let clubs = CardSet::all().suit(Club);
+        assert_eq!(13, clubs.len());
+        assert!(clubs.iter().all(|card| card.suit == Club));
+        assert_eq!(vec![Ace.of(Club)], hand.intersection(clubs).iter().collect::<Vec<_>>());
+        assert_eq!(2, hand.difference(clubs).len());
+        assert_eq!(15, hand.union(clubs).len());
+        assert!(hand.suit(Heart).is_subset(hand));
+        assert!(!hand.is_subset(clubs));
+        assert_eq!(Some(Ace.of(Club)), hand.iter().next_back());
+        assert_eq!("\u{2660}Q \u{2665}T \u{2663}A", format!("{}", hand));
+    }
+
+    #[test]
+    fn converts_to_and_from_btree_sets() {
+        let cards = vec![King.of(Diamond), Three.of(Spade)]
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        let set = CardSet::from(&cards);
+        assert_eq!(cards, BTreeSet::from(set));
+        assert_eq!(set, CardSet::from_bits(set.bits()));
+    }
+}
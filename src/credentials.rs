@@ -0,0 +1,178 @@
+use error::Error;
+use error::Result;
+use game_status::PlayerName;
+use hearts_client::Password;
+
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+
+/// Environment variable the client reads its password from.
+pub const PASSWORD_VARIABLE: &'static str = "HEARTS_PASSWORD";
+
+/// Finds the password for a player: from the `--password` flag if one was given, which is
+/// discouraged as the flag shows up in `ps` output and shell history, else from the password
+/// file, else from `HEARTS_PASSWORD`, else by asking on the terminal.
+pub fn password(
+    player_name: &PlayerName,
+    flag: Option<&str>,
+    password_file: Option<&str>,
+) -> Result<Password> {
+    if let Some(password) = flag {
+        warn!(
+            "--password can be seen by other users, prefer --password-file or {}",
+            PASSWORD_VARIABLE
+        );
+        return Ok(Password::new(password));
+    }
+    if let Some(password_file) = password_file {
+        return read_password_file(password_file);
+    }
+    match env::var(PASSWORD_VARIABLE) {
+        Ok(password) => non_empty(password, PASSWORD_VARIABLE),
+        Err(env::VarError::NotUnicode(_)) => {
+            Err(Error::Game(format!("{} is not valid unicode", PASSWORD_VARIABLE)))
+        }
+        Err(env::VarError::NotPresent) => prompt(player_name),
+    }
+}
+
+/// Reads a password from the first line of a file, which only its owner may read or write.
+pub fn read_password_file<P: AsRef<Path>>(path: P) -> Result<Password> {
+    let path = path.as_ref();
+    check_permissions(path)?;
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    let password = contents.lines().next().unwrap_or_default().to_owned();
+    non_empty(password, &path.display().to_string())
+}
+
+/// Fails unless only the owner of the file, which holds passwords, may read or write it.
+#[cfg(unix)]
+pub fn check_permissions(path: &Path) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(path)?.permissions().mode();
+    if mode & 0o077 != 0 {
+        return Err(Error::Game(format!(
+            "{} can be read or written by other users (mode {:o}), chmod 600 it",
+            path.display(),
+            mode & 0o777
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn check_permissions(path: &Path) -> Result<()> {
+    fs::metadata(path)?;
+    Ok(())
+}
+
+fn non_empty(password: String, source: &str) -> Result<Password> {
+    if password.is_empty() {
+        Err(Error::Game(format!("The password in {} is empty", source)))
+    } else {
+        Ok(Password::new(password))
+    }
+}
+
+/// Asks for the password on the terminal, without echoing it.
+fn prompt(player_name: &PlayerName) -> Result<Password> {
+    if !terminal::is_terminal() {
+        return Err(Error::Game(format!(
+            "No password given, use --password-file or set {}",
+            PASSWORD_VARIABLE
+        )));
+    }
+    eprint!("Password for {}: ", player_name);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    terminal::without_echo(|| io::stdin().read_line(&mut line))?;
+    let password = line.lines().next().unwrap_or_default().to_owned();
+    non_empty(password, "the prompt")
+}
+
+#[cfg(unix)]
+mod terminal {
+    use libc;
+
+    use std::mem;
+
+    pub fn is_terminal() -> bool {
+        unsafe { libc::isatty(libc::STDIN_FILENO) == 1 }
+    }
+
+    /// Runs the function with the terminal's echo turned off, restoring it afterwards.
+    pub fn without_echo<A, F: FnOnce() -> A>(f: F) -> A {
+        let mut original: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+            return f();
+        }
+        let mut silent = original;
+        silent.c_lflag &= !libc::ECHO;
+        silent.c_lflag |= libc::ECHONL;
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &silent) };
+        let result = f();
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &original) };
+        result
+    }
+}
+
+#[cfg(not(unix))]
+mod terminal {
+    pub fn is_terminal() -> bool {
+        false
+    }
+
+    pub fn without_echo<A, F: FnOnce() -> A>(f: F) -> A {
+        f()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    use std::os::unix::fs::PermissionsExt;
+
+    #[cfg(unix)]
+    fn password_file(name: &str, contents: &str, mode: u32) -> String {
+        let path = env::temp_dir().join(format!("hearts-{}-{}", name, ::std::process::id()));
+        File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn reads_first_line_of_private_file() {
+        let path = password_file("private", "secret\nignored\n", 0o600);
+        assert_eq!(Password::new("secret"), read_password_file(&path).unwrap());
+
+        let flag = password(&PlayerName::new("Bill"), Some("flag"), Some(&path)).unwrap();
+        assert_eq!(Password::new("flag"), flag);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn hides_password_from_debug() {
+        assert_eq!("Password(***)", format!("{:?}", Password::new("secret")));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn rejects_shared_or_empty_files() {
+        let shared = password_file("shared", "secret\n", 0o644);
+        let empty = password_file("empty", "\n", 0o600);
+        assert!(read_password_file(&shared).is_err());
+        assert!(read_password_file(&empty).is_err());
+        fs::remove_file(shared).unwrap();
+        fs::remove_file(empty).unwrap();
+    }
+}
@@ -0,0 +1,332 @@
+mod round;
+
+pub use engine::round::Round;
+pub use engine::round::PLAYER_COUNT;
+pub use engine::round::describe;
+
+use card::Rank;
+use card::Suit;
+use game_status::GameInstanceState;
+use game_status::RoundParameters;
+use game_status::PlayerName;
+use game_status::PassDirection;
+use strategy::CardStrategy;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::StdRng;
+
+use std::collections::BTreeMap;
+
+pub const ROUND_COUNT: u32 = 5;
+
+/// Plays complete games between four strategies without a server.
+pub struct Engine {
+    game_id: String,
+    strategies: Vec<Box<CardStrategy>>,
+    rng: StdRng,
+}
+
+#[derive(Debug, Clone)]
+pub struct RoundResult {
+    pub round_id: u32,
+    pub points: BTreeMap<PlayerName, i32>,
+    pub shooter: Option<PlayerName>,
+    pub moon_points: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameResult {
+    pub game_id: String,
+    pub rounds: Vec<RoundResult>,
+}
+
+impl Engine {
+    /// Strategies are seated in the given order, each one's left participant being the next.
+    pub fn new(strategies: Vec<Box<CardStrategy>>, seed: usize) -> Engine {
+        assert_eq!(PLAYER_COUNT, strategies.len(), "Hearts needs four players");
+        let mut rng = StdRng::from_seed(&[seed]);
+        Engine {
+            game_id: format!("{:016x}{:016x}", rng.gen::<u64>(), rng.gen::<u64>()),
+            strategies: strategies,
+            rng: rng,
+        }
+    }
+
+    pub fn play_game(&mut self) -> GameResult {
+        GameResult {
+            game_id: self.game_id.clone(),
+            rounds: (1..ROUND_COUNT + 1)
+                .map(|round_id| self.play_round(round_id))
+                .collect(),
+        }
+    }
+
+    pub fn play_round(&mut self, round_id: u32) -> RoundResult {
+        let seat_order = self.strategies
+            .iter()
+            .map(|strategy| strategy.player_name().clone())
+            .collect();
+        let mut round = Round::new(round_parameters(round_id), seat_order, &mut self.rng);
+
+        round.start_passing();
+        for strategy in &mut self.strategies {
+            let player_name = strategy.player_name().clone();
+            let game_status =
+                round.game_status(&self.game_id, GameInstanceState::Running, &player_name);
+            let cards = strategy.pass_cards(&game_status);
+            if let Err(e) = round.pass_cards(&player_name, cards) {
+                warn!("{} passed invalid cards: {}", player_name, e);
+                let fallback = game_status
+                    .my_current_hand
+                    .iter()
+                    .take(game_status.round_parameters.cards_to_pass())
+                    .collect();
+                round.pass_cards(&player_name, fallback).unwrap();
+            }
+        }
+        round.exchange_cards();
+
+        while let Some(player_name) = round.turn().cloned() {
+            let game_status =
+                round.game_status(&self.game_id, GameInstanceState::Running, &player_name);
+            let chosen = self.strategy(&player_name).play_card(&game_status);
+            let card = if round.valid_plays(&player_name).contains(&chosen) {
+                chosen
+            } else {
+                debug!("{} chose invalid card {}", player_name, chosen);
+                round.valid_plays(&player_name).into_iter().next().expect(
+                    "No valid cards to play!",
+                )
+            };
+            round.play_card(&player_name, card).unwrap();
+        }
+
+        RoundResult {
+            round_id: round_id,
+            points: self.strategies
+                .iter()
+                .map(|strategy| {
+                    let player_name = strategy.player_name();
+                    (player_name.clone(), round.points(player_name))
+                })
+                .collect(),
+            shooter: round.shooter().cloned(),
+            moon_points: round
+                .parameters()
+                .card_points
+                .values()
+                .filter(|&&points| points > 0)
+                .sum(),
+        }
+    }
+
+    fn strategy(&mut self, player_name: &PlayerName) -> &mut Box<CardStrategy> {
+        self.strategies
+            .iter_mut()
+            .find(|strategy| strategy.player_name() == player_name)
+            .expect("No strategy for player")
+    }
+}
+
+impl RoundResult {
+    /// Points for the round once a moon shot is accounted for: the shooter gives up the
+    /// points they took and everyone else takes them instead.
+    pub fn scores(&self) -> BTreeMap<PlayerName, i32> {
+        self.points
+            .iter()
+            .map(|(player_name, &points)| {
+                let score = match self.shooter {
+                    Some(ref shooter) if shooter == player_name => points - self.moon_points,
+                    Some(_) => points + self.moon_points,
+                    None => points,
+                };
+                (player_name.clone(), score)
+            })
+            .collect()
+    }
+}
+
+impl GameResult {
+    pub fn scores(&self) -> BTreeMap<PlayerName, i32> {
+        let mut scores = BTreeMap::new();
+        for round in &self.rounds {
+            for (player_name, score) in round.scores() {
+                *scores.entry(player_name).or_insert(0) += score;
+            }
+        }
+        scores
+    }
+}
+
+/// Round parameters as the competition server sets them: hearts and the queen of spades
+/// count against you, and in rounds 3 and 5 diamonds or clubs count in your favour.
+pub fn round_parameters(round_id: u32) -> RoundParameters {
+    let mut card_points = BTreeMap::new();
+    for rank in Rank::all() {
+        card_points.insert(rank.of(Suit::Heart), 1);
+    }
+    card_points.insert(Rank::Queen.of(Suit::Spade), 13);
+    let negative_suit = match round_id {
+        3 => Some(Suit::Diamond),
+        5 => Some(Suit::Club),
+        _ => None,
+    };
+    if let Some(suit) = negative_suit {
+        for rank in Rank::all() {
+            card_points.insert(rank.of(suit), -1);
+        }
+    }
+    RoundParameters {
+        round_id: round_id,
+        initiation_phase_in_seconds: 5,
+        passing_phase_in_seconds: 15,
+        dealing_phase_in_seconds: 5,
+        finishing_phase_in_seconds: 5,
+        number_of_cards_to_be_passed: 3,
+        pass_direction: PassDirection::Right,
+        card_points: card_points,
+    }
+}
+
+/// North, East, South and West, the players of the rounds tests play.
+#[cfg(test)]
+pub fn test_players() -> Vec<PlayerName> {
+    ["North", "East", "South", "West"]
+        .iter()
+        .map(|name| PlayerName::new(*name))
+        .collect()
+}
+
+/// A round dealt to `test_players` from the seed.
+#[cfg(test)]
+pub fn test_round(parameters: RoundParameters, seed: usize) -> Round {
+    let mut rng = StdRng::from_seed(&[seed]);
+    Round::new(parameters, test_players(), &mut rng)
+}
+
+/// Plays a test round through, everyone passing their first cards and playing their lowest
+/// valid card. `observe` is shown the round before each pass and play, with whose it is.
+#[cfg(test)]
+pub fn play_test_round<F: FnMut(&Round, &PlayerName)>(round: &mut Round, mut observe: F) {
+    round.start_passing();
+    for player_name in &test_players() {
+        observe(round, player_name);
+        let status = round.game_status("", GameInstanceState::Running, player_name);
+        let cards_to_pass = round.parameters().cards_to_pass();
+        let cards = status.my_current_hand.iter().take(cards_to_pass).collect();
+        round.pass_cards(player_name, cards).unwrap();
+    }
+    assert!(round.exchange_cards());
+    while let Some(player_name) = round.turn().cloned() {
+        observe(round, &player_name);
+        let card = round.valid_plays(&player_name).iter().next().unwrap();
+        round.play_card(&player_name, card).unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use game_status::HeartsGameInstanceState;
+    use strategy::CardStrategy;
+    use strategy::DefensiveCardStrategy;
+    use strategy::SimpleCardStrategy;
+
+    fn total_points(round_id: u32) -> i32 {
+        round_parameters(round_id).card_points.values().sum()
+    }
+
+    fn strategies() -> Vec<Box<CardStrategy>> {
+        vec![
+            Box::new(DefensiveCardStrategy::new(PlayerName::new("North"))),
+            Box::new(SimpleCardStrategy::new(PlayerName::new("East"))),
+            Box::new(DefensiveCardStrategy::new(PlayerName::new("South"))),
+            Box::new(SimpleCardStrategy::new(PlayerName::new("West"))),
+        ]
+    }
+
+    #[test]
+    fn all_points_are_taken() {
+        let result = Engine::new(strategies(), 42).play_game();
+
+        assert_eq!(ROUND_COUNT as usize, result.rounds.len());
+        for round in &result.rounds {
+            assert_eq!(
+                total_points(round.round_id),
+                round.points.values().sum::<i32>()
+            );
+        }
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        let first = Engine::new(strategies(), 7).play_game();
+        let second = Engine::new(strategies(), 7).play_game();
+
+        assert_eq!(first.game_id, second.game_id);
+        assert_eq!(first.scores(), second.scores());
+    }
+
+    #[test]
+    fn moon_shot_scores() {
+        let mut points = BTreeMap::new();
+        points.insert(PlayerName::new("North"), 26);
+        points.insert(PlayerName::new("East"), 0);
+        let result = RoundResult {
+            round_id: 1,
+            points: points,
+            shooter: Some(PlayerName::new("North")),
+            moon_points: 26,
+        };
+
+        let scores = result.scores();
+        assert_eq!(0, scores[&PlayerName::new("North")]);
+        assert_eq!(26, scores[&PlayerName::new("East")]);
+    }
+    #[test]
+    fn passes_in_every_direction() {
+        let players = test_players();
+        let directions = vec![
+            (PassDirection::Left, 1),
+            (PassDirection::Across, 2),
+            (PassDirection::Right, 3),
+        ];
+        for (direction, seats) in directions {
+            let mut parameters = round_parameters(1);
+            parameters.pass_direction = direction;
+            let mut round = test_round(parameters, 1);
+            let mut passed = BTreeMap::new();
+            play_test_round(&mut round, |round, player_name| {
+                if round.game_state() == HeartsGameInstanceState::Passing {
+                    let status = round.game_status("", GameInstanceState::Running, player_name);
+                    let cards = status.my_current_hand.iter().take(3).collect::<Vec<_>>();
+                    passed.insert(player_name.clone(), cards);
+                }
+            });
+
+            for (index, player_name) in players.iter().enumerate() {
+                let receiver = &players[(index + seats) % PLAYER_COUNT];
+                let status = round.game_status("", GameInstanceState::Running, receiver);
+                let received = status.cards_passed_to_me.iter().collect::<Vec<_>>();
+                assert_eq!(passed[player_name], received);
+                assert_eq!(Some(player_name), status.pass_giver(receiver));
+            }
+        }
+    }
+
+    #[test]
+    fn hold_keeps_hands() {
+        let mut parameters = round_parameters(1);
+        parameters.pass_direction = PassDirection::Hold;
+        let players = test_players();
+        let mut round = test_round(parameters, 1);
+        round.start_passing();
+
+        assert!(round.has_passed(&players[0]));
+        assert!(round.exchange_cards());
+        let status = round.game_status("", GameInstanceState::Running, &players[0]);
+        assert!(status.cards_passed_to_me.is_empty());
+        assert_eq!(status.my_initial_hand, status.my_current_hand);
+    }
+}
@@ -0,0 +1,344 @@
+use card::Card;
+use card::CardSet;
+use card::Rank;
+use card::Suit;
+use deal::Deal;
+use deal::DealCard;
+use error::Error;
+use error::Result;
+use game_status::GameStatus;
+use game_status::GameInstanceState;
+use game_status::RoundState;
+use game_status::HeartsGameInstanceState;
+use game_status::RoundParameters;
+use game_status::GameParticipant;
+use game_status::PlayerName;
+use opponents::Opponents;
+use rules;
+
+use rand::Rng;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+pub const PLAYER_COUNT: usize = 4;
+const DEAL_COUNT: u32 = 13;
+
+/// The rules state of a single round: hands, passing, the deals played so far and whose
+/// turn it is.
+pub struct Round {
+    parameters: RoundParameters,
+    round_state: RoundState,
+    game_state: HeartsGameInstanceState,
+    seat_order: Vec<PlayerName>,
+    hands: BTreeMap<PlayerName, Hand>,
+    game_deals: Vec<Deal>,
+    in_progress_deal: Deal,
+    turn: Option<PlayerName>,
+}
+
+#[derive(Default)]
+struct Hand {
+    initial: CardSet,
+    passed_by: CardSet,
+    passed_to: CardSet,
+    current: CardSet,
+    pending_pass: Option<CardSet>,
+}
+
+impl Round {
+    /// Shuffles and deals a new round. Players are given in playing order, each player's
+    /// left participant being the next one in the list.
+    pub fn new<R: Rng>(
+        parameters: RoundParameters,
+        seat_order: Vec<PlayerName>,
+        rng: &mut R,
+    ) -> Round {
+        let mut deck = Card::all().into_iter().collect::<Vec<_>>();
+        rng.shuffle(&mut deck);
+        let hands = seat_order
+            .iter()
+            .zip(deck.chunks(deck.len() / PLAYER_COUNT))
+            .map(|(player_name, cards)| {
+                let cards = cards.iter().collect::<CardSet>();
+                (
+                    player_name.clone(),
+                    Hand {
+                        initial: cards,
+                        current: cards,
+                        ..Hand::default()
+                    },
+                )
+            })
+            .collect();
+        Round {
+            parameters: parameters,
+            round_state: RoundState::Initiated,
+            game_state: HeartsGameInstanceState::Initiated,
+            seat_order: seat_order,
+            hands: hands,
+            game_deals: Vec::new(),
+            in_progress_deal: Self::new_deal(1, None),
+            turn: None,
+        }
+    }
+
+    pub fn parameters(&self) -> &RoundParameters {
+        &self.parameters
+    }
+
+    pub fn round_state(&self) -> RoundState {
+        self.round_state
+    }
+
+    pub fn game_state(&self) -> HeartsGameInstanceState {
+        self.game_state
+    }
+
+    pub fn turn(&self) -> Option<&PlayerName> {
+        self.turn.as_ref()
+    }
+
+    pub fn start_passing(&mut self) {
+        self.round_state = RoundState::Running;
+        self.game_state = HeartsGameInstanceState::Passing;
+    }
+
+    /// Whether the player has passed their cards, or has none to pass.
+    pub fn has_passed(&self, player_name: &PlayerName) -> bool {
+        self.hands.get(player_name).map_or(false, |hand| {
+            hand.pending_pass.is_some() || self.parameters.cards_to_pass() == 0
+        })
+    }
+
+    pub fn pass_cards(&mut self, player_name: &PlayerName, cards: Vec<Card>) -> Result<()> {
+        let number_of_cards = self.parameters.cards_to_pass();
+        let hand = self.hands.get_mut(player_name).ok_or_else(|| {
+            Error::game(Some("Not playing in this game"))
+        })?;
+        let cards = cards.into_iter().collect::<CardSet>();
+        if hand.pending_pass.is_some() {
+            Err(Error::game(Some("Cards already passed")))
+        } else if cards.len() != number_of_cards {
+            Err(Error::game(
+                Some(format!("Exactly {} cards must be passed", number_of_cards)),
+            ))
+        } else if !cards.is_subset(hand.current) {
+            Err(Error::game(Some("Cannot pass cards not in hand")))
+        } else {
+            hand.pending_pass = Some(cards);
+            Ok(())
+        }
+    }
+
+    /// Once every player has passed, hands the cards over and starts dealing.
+    pub fn exchange_cards(&mut self) -> bool {
+        if self.seat_order.iter().any(|player_name| !self.has_passed(player_name)) {
+            return false;
+        }
+        let seats = self.parameters.pass_direction.seats_to_the_left(PLAYER_COUNT);
+        for player_name in self.seat_order.clone() {
+            let cards = self.hands
+                .get_mut(&player_name)
+                .and_then(|hand| hand.pending_pass.take())
+                .unwrap_or_default();
+            if let Some(seats) = seats {
+                let receiver = self.seat_offset(&player_name, seats);
+                {
+                    let hand = self.hands.get_mut(&player_name).unwrap();
+                    hand.current = hand.current.difference(cards);
+                    hand.passed_by = cards;
+                }
+                self.hands.get_mut(&receiver).unwrap().passed_to = cards;
+            }
+        }
+        for hand in self.hands.values_mut() {
+            hand.current = hand.current.union(hand.passed_to);
+        }
+
+        let two_of_clubs = Rank::Two.of(Suit::Club);
+        self.turn = self.hands
+            .iter()
+            .find(|&(_, hand)| hand.current.contains(&two_of_clubs))
+            .map(|(player_name, _)| player_name.clone());
+        self.game_state = HeartsGameInstanceState::Dealing;
+        true
+    }
+
+    pub fn valid_plays(&self, player_name: &PlayerName) -> CardSet {
+        self.hands
+            .get(player_name)
+            .map(|hand| {
+                rules::legal_plays(
+                    hand.current,
+                    &self.game_deals,
+                    Some(&self.in_progress_deal),
+                    &self.parameters,
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn play_card(&mut self, player_name: &PlayerName, card: Card) -> Result<()> {
+        if self.turn.as_ref() != Some(player_name) {
+            return Err(Error::game(Some("Not your turn")));
+        }
+        if !self.valid_plays(player_name).contains(&card) {
+            return Err(Error::game(Some(format!("Cannot play {}", card))));
+        }
+        self.hands.get_mut(player_name).unwrap().current.remove(&card);
+        {
+            let deal = &mut self.in_progress_deal;
+            if deal.deal_cards.is_empty() {
+                deal.initiator = Some(player_name.clone());
+                deal.suit = Some(card.suit);
+            }
+            deal.deal_cards.push(DealCard {
+                player_name: player_name.clone(),
+                card: card,
+            });
+        }
+
+        if self.in_progress_deal.deal_cards.len() < PLAYER_COUNT {
+            self.turn = Some(self.left_of(player_name));
+        } else {
+            let winner = Self::deal_winner(&self.in_progress_deal);
+            self.in_progress_deal.deal_winner = Some(winner.clone());
+            self.game_deals.push(self.in_progress_deal.clone());
+            let deal_number = self.in_progress_deal.deal_number;
+            if deal_number < DEAL_COUNT {
+                self.in_progress_deal = Self::new_deal(deal_number + 1, Some(winner.clone()));
+                self.turn = Some(winner);
+            } else {
+                self.turn = None;
+                self.round_state = RoundState::Finished;
+                self.game_state = HeartsGameInstanceState::Finished;
+            }
+        }
+        Ok(())
+    }
+
+    /// Points taken so far by the player, as the server reports them.
+    pub fn points(&self, player_name: &PlayerName) -> i32 {
+        self.game_deals
+            .iter()
+            .filter(|deal| deal.deal_winner.as_ref() == Some(player_name))
+            .flat_map(|deal| deal.deal_cards.iter())
+            .map(|deal_card| self.parameters.points(&deal_card.card))
+            .sum()
+    }
+
+    /// The player who took every card worth points, if the round is over and anyone did.
+    pub fn shooter(&self) -> Option<&PlayerName> {
+        if self.round_state != RoundState::Finished {
+            return None;
+        }
+        let mut winners = self.game_deals
+            .iter()
+            .filter(|deal| {
+                deal.deal_cards.iter().any(|deal_card| {
+                    self.parameters.points(&deal_card.card) > 0
+                })
+            })
+            .filter_map(|deal| deal.deal_winner.as_ref())
+            .collect::<BTreeSet<_>>()
+            .into_iter();
+        match (winners.next(), winners.next()) {
+            (Some(shooter), None) => Some(shooter),
+            _ => None,
+        }
+    }
+
+    pub fn game_status(
+        &self,
+        game_id: &str,
+        game_state: GameInstanceState,
+        player_name: &PlayerName,
+    ) -> GameStatus {
+        let hand = self.hands.get(player_name);
+        let cards = |select: fn(&Hand) -> CardSet| hand.map(select).unwrap_or_default();
+        GameStatus {
+            current_game_id: game_id.to_owned(),
+            current_game_state: game_state,
+            current_round_id: self.parameters.round_id,
+            current_round_state: self.round_state,
+            round_parameters: self.parameters.clone(),
+            game_state: self.game_state,
+            game_state_description: describe(self.game_state).to_owned(),
+            game_players: self.participants(),
+            my_initial_hand: cards(|hand| hand.initial),
+            cards_passed_by_me: cards(|hand| hand.passed_by),
+            cards_passed_to_me: cards(|hand| hand.passed_to),
+            my_final_hand: cards(|hand| hand.final_hand()),
+            my_current_hand: cards(|hand| hand.current),
+            game_deals: self.game_deals.clone(),
+            in_progress_deal: Some(self.in_progress_deal.clone()),
+            is_my_turn: self.game_state == HeartsGameInstanceState::Dealing &&
+                self.turn.as_ref() == Some(player_name),
+            opponents: Opponents::default(),
+        }
+    }
+
+    fn participants(&self) -> Vec<GameParticipant> {
+        self.seat_order
+            .iter()
+            .map(|player_name| {
+                GameParticipant {
+                    team_name: player_name.clone(),
+                    left_participant: self.left_of(player_name),
+                    number_of_cards_in_hand: self.hands
+                        .get(player_name)
+                        .map_or(0, |hand| hand.current.len() as u32),
+                    has_turn: self.turn.as_ref() == Some(player_name),
+                    current_score: self.points(player_name),
+                }
+            })
+            .collect()
+    }
+
+    fn new_deal(deal_number: u32, initiator: Option<PlayerName>) -> Deal {
+        Deal {
+            deal_number: deal_number,
+            initiator: initiator,
+            suit: None,
+            deal_cards: Vec::new(),
+            deal_winner: None,
+        }
+    }
+
+    fn deal_winner(deal: &Deal) -> PlayerName {
+        deal.deal_cards
+            .iter()
+            .filter(|deal_card| Some(deal_card.card.suit) == deal.suit)
+            .max_by_key(|deal_card| deal_card.card.rank)
+            .map(|deal_card| deal_card.player_name.clone())
+            .expect("Deal has no cards")
+    }
+
+    fn left_of(&self, player_name: &PlayerName) -> PlayerName {
+        self.seat_offset(player_name, 1)
+    }
+
+    fn seat_offset(&self, player_name: &PlayerName, offset: usize) -> PlayerName {
+        let seats = &self.seat_order;
+        let index = seats.iter().position(|other| other == player_name).unwrap();
+        seats[(index + offset) % seats.len()].clone()
+    }
+}
+
+impl Hand {
+    fn final_hand(&self) -> CardSet {
+        self.initial.difference(self.passed_by).union(self.passed_to)
+    }
+}
+
+pub fn describe(game_state: HeartsGameInstanceState) -> &'static str {
+    match game_state {
+        HeartsGameInstanceState::NotStarted => "Hearts game is not started",
+        HeartsGameInstanceState::Initiated => "Hearts game is initiated",
+        HeartsGameInstanceState::Passing => "Passing cards",
+        HeartsGameInstanceState::Dealing => "Playing",
+        HeartsGameInstanceState::Finished => "Hearts game is finished",
+        HeartsGameInstanceState::Cancelled => "Hearts game is cancelled",
+    }
+}
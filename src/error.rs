@@ -2,8 +2,11 @@ use std::error::Error as StdError;
 use std::fmt;
 
 use std::io::Error as IoError;
+use std::io::ErrorKind;
 use serde_json::error::Error as SerdeJsonError;
 use hyper::error::Error as HyperError;
+use hyper::status::StatusCode;
+use toml::de::Error as TomlError;
 
 pub type Result<T> = ::std::result::Result<T, Error>;
 
@@ -11,14 +14,27 @@ pub type Result<T> = ::std::result::Result<T, Error>;
 pub enum Error {
     Game(String),
     Http(HyperError),
+    /// A response other than 200 OK, with its body.
+    Status(StatusCode, String),
     Io(IoError),
     Json(SerdeJsonError),
     Parsing(String),
+    Toml(TomlError),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        f.write_str(self.description())
+        match *self {
+            Error::Status(status, ref body) if body.is_empty() => {
+                write!(f, "Server responded {}", status)
+            }
+            Error::Status(status, ref body) => write!(f, "Server responded {}: {}", status, body),
+            Error::Http(ref e) => write!(f, "{}", e),
+            Error::Io(ref e) => write!(f, "{}", e),
+            Error::Json(ref e) => write!(f, "{}", e),
+            Error::Toml(ref e) => write!(f, "{}", e),
+            _ => f.write_str(self.description()),
+        }
     }
 }
 
@@ -27,9 +43,11 @@ impl StdError for Error {
         match *self {
             Error::Game(ref s) => s,
             Error::Http(ref e) => e.description(),
+            Error::Status(..) => "Unexpected HTTP status",
             Error::Io(ref e) => e.description(),
             Error::Json(ref e) => e.description(),
             Error::Parsing(ref s) => s,
+            Error::Toml(ref e) => e.description(),
         }
     }
 
@@ -38,6 +56,7 @@ impl StdError for Error {
             Error::Http(ref e) => Some(e),
             Error::Io(ref e) => Some(e),
             Error::Json(ref e) => Some(e),
+            Error::Toml(ref e) => Some(e),
             _ => None,
         }
     }
@@ -48,6 +67,33 @@ impl Error {
         Error::Parsing(format!("Error while parsing \"{}\" as {}", string, source))
     }
 
+    /// Whether the same request might succeed if tried again: the server could not be
+    /// reached, or it is failing or busy, rather than rejecting the request itself.
+    pub fn is_retryable(&self) -> bool {
+        match *self {
+            Error::Http(_) | Error::Io(_) => true,
+            Error::Status(status, _) => {
+                status.is_server_error() || status == StatusCode::RequestTimeout ||
+                    status == StatusCode::TooManyRequests
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a request that changes the game can be tried again without the risk of it
+    /// being made twice: it never reached the server, or the server said it did not take
+    /// it on.
+    pub fn is_retryable_submission(&self) -> bool {
+        match *self {
+            Error::Http(HyperError::Io(ref e)) |
+            Error::Io(ref e) => e.kind() == ErrorKind::ConnectionRefused,
+            Error::Status(status, _) => {
+                status == StatusCode::ServiceUnavailable || status == StatusCode::TooManyRequests
+            }
+            _ => false,
+        }
+    }
+
     pub fn game<A: Into<String>>(cause: Option<A>) -> Error {
         match cause {
             Some(string) => Error::Game(string.into()),
@@ -73,3 +119,9 @@ impl From<SerdeJsonError> for Error {
         Error::Json(err)
     }
 }
+
+impl From<TomlError> for Error {
+    fn from(err: TomlError) -> Error {
+        Error::Toml(err)
+    }
+}
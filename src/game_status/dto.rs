@@ -5,6 +5,7 @@ use game_status::RoundState;
 use game_status::HeartsGameInstanceState;
 use game_status::GameParticipant;
 use game_status::PlayerName;
+use game_status::PassDirection;
 
 use card::Card;
 use card::dto::CardDto;
@@ -12,6 +13,7 @@ use deal::Deal;
 use deal::dto::DealDto;
 use error::Error;
 use error::Result;
+use opponents::Opponents;
 
 use std::convert::TryFrom;
 use std::convert::TryInto;
@@ -99,6 +101,7 @@ impl TryFrom<GameStatusDto> for GameStatus {
                 .collect::<Result<_>>()?,
             in_progress_deal: my_in_progress_deal?,
             is_my_turn: dto.is_my_turn,
+            opponents: Opponents::default(),
         })
     }
 }
@@ -121,27 +124,27 @@ impl<'a> From<&'a GameStatus> for GameStatusDto {
             my_initial_hand: game_status
                 .my_initial_hand
                 .iter()
-                .map(CardDto::from)
+                .map(|card| CardDto::from(&card))
                 .collect(),
             cards_passed_by_me: game_status
                 .cards_passed_by_me
                 .iter()
-                .map(CardDto::from)
+                .map(|card| CardDto::from(&card))
                 .collect(),
             cards_passed_to_me: game_status
                 .cards_passed_to_me
                 .iter()
-                .map(CardDto::from)
+                .map(|card| CardDto::from(&card))
                 .collect(),
             my_final_hand: game_status
                 .my_final_hand
                 .iter()
-                .map(CardDto::from)
+                .map(|card| CardDto::from(&card))
                 .collect(),
             my_current_hand: game_status
                 .my_current_hand
                 .iter()
-                .map(CardDto::from)
+                .map(|card| CardDto::from(&card))
                 .collect(),
             my_game_deals: game_status.game_deals.iter().map(DealDto::from).collect(),
             my_in_progress_deal: game_status.in_progress_deal.as_ref().map(DealDto::from),
@@ -164,6 +167,8 @@ pub struct RoundParametersDto {
     finishing_phase_in_seconds: u32,
     #[serde(rename = "NumberOfCardsTobePassed")]
     number_of_cards_to_be_passed: u32,
+    #[serde(rename = "PassDirection", default)]
+    pass_direction: Option<String>,
     #[serde(rename = "CardPoints")]
     card_points: Vec<CardPointsDto>,
 }
@@ -171,6 +176,11 @@ pub struct RoundParametersDto {
 impl TryFrom<RoundParametersDto> for RoundParameters {
     type Error = Error;
     fn try_from(dto: RoundParametersDto) -> Result<RoundParameters> {
+        let pass_direction = match dto.pass_direction {
+            Some(ref direction) => PassDirection::from_str(direction)?,
+            None if dto.number_of_cards_to_be_passed == 0 => PassDirection::Hold,
+            None => PassDirection::default(),
+        };
         Ok(RoundParameters {
             round_id: dto.round_id,
             initiation_phase_in_seconds: dto.initiation_phase_in_seconds,
@@ -178,6 +188,7 @@ impl TryFrom<RoundParametersDto> for RoundParameters {
             dealing_phase_in_seconds: dto.dealing_phase_in_seconds,
             finishing_phase_in_seconds: dto.finishing_phase_in_seconds,
             number_of_cards_to_be_passed: dto.number_of_cards_to_be_passed,
+            pass_direction: pass_direction,
             card_points: dto.card_points
                 .into_iter()
                 .map(CardPointsDto::try_into)
@@ -195,6 +206,7 @@ impl<'a> From<&'a RoundParameters> for RoundParametersDto {
             dealing_phase_in_seconds: entity.dealing_phase_in_seconds,
             finishing_phase_in_seconds: entity.finishing_phase_in_seconds,
             number_of_cards_to_be_passed: entity.number_of_cards_to_be_passed,
+            pass_direction: Some(entity.pass_direction.into()),
             card_points: entity
                 .card_points
                 .iter()
@@ -1,13 +1,17 @@
 pub mod dto;
+mod text;
 
 use card::Card;
+use card::CardSet;
 use deal::Deal;
+use opponents::Opponents;
+use rules;
 
-use std::collections::BTreeSet;
 use std::collections::BTreeMap;
 use std::fmt;
+use std::time::Duration;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GameStatus {
     pub current_game_id: String,
     pub current_game_state: GameInstanceState,
@@ -17,37 +21,73 @@ pub struct GameStatus {
     pub game_state: HeartsGameInstanceState,
     pub game_state_description: String,
     pub game_players: Vec<GameParticipant>,
-    pub my_initial_hand: BTreeSet<Card>,
-    pub cards_passed_by_me: BTreeSet<Card>,
-    pub cards_passed_to_me: BTreeSet<Card>,
-    pub my_final_hand: BTreeSet<Card>,
-    pub my_current_hand: BTreeSet<Card>,
+    pub my_initial_hand: CardSet,
+    pub cards_passed_by_me: CardSet,
+    pub cards_passed_to_me: CardSet,
+    pub my_final_hand: CardSet,
+    pub my_current_hand: CardSet,
     pub game_deals: Vec<Deal>,
     pub in_progress_deal: Option<Deal>,
     pub is_my_turn: bool,
+    /// What is known of the other players at the table from earlier games. The server does
+    /// not send this; the client fills it in from its model of the opponents.
+    pub opponents: Opponents,
 }
 
 impl GameStatus {
-    pub fn unplayed_cards(&self) -> BTreeSet<Card> {
-        let mut cards = Card::all();
+    pub fn unplayed_cards(&self) -> CardSet {
+        let played = self.game_deals
+            .iter()
+            .chain(self.in_progress_deal.iter())
+            .flat_map(|deal| deal.deal_cards.iter())
+            .map(|deal_card| deal_card.card)
+            .collect::<CardSet>();
+        Card::all().difference(played).difference(self.my_current_hand)
+    }
 
-        for deal in &self.game_deals {
-            for deal_card in &deal.deal_cards {
-                cards.remove(&deal_card.card);
-            }
-        }
+    pub fn pass_direction(&self) -> PassDirection {
+        self.round_parameters.pass_direction
+    }
 
-        if let Some(ref deal) = self.in_progress_deal {
-            for deal_card in &deal.deal_cards {
-                cards.remove(&deal_card.card);
-            }
-        }
+    /// The player who receives the cards the given player passes, if cards are passed.
+    pub fn pass_receiver<'a>(&'a self, player_name: &'a PlayerName) -> Option<&'a PlayerName> {
+        self.pass_direction()
+            .seats_to_the_left(self.game_players.len())
+            .and_then(|seats| self.seat_to_the_left(player_name, seats))
+    }
+
+    /// The player who passes their cards to the given player, if cards are passed.
+    pub fn pass_giver<'a>(&'a self, player_name: &'a PlayerName) -> Option<&'a PlayerName> {
+        let players = self.game_players.len();
+        self.pass_direction()
+            .seats_to_the_left(players)
+            .and_then(|seats| self.seat_to_the_left(player_name, players - seats))
+    }
 
-        for card in &self.my_current_hand {
-            cards.remove(&card);
+    fn seat_to_the_left<'a>(
+        &'a self,
+        player_name: &'a PlayerName,
+        seats: usize,
+    ) -> Option<&'a PlayerName> {
+        let mut seat = Some(player_name);
+        for _ in 0..seats {
+            seat = seat.and_then(|current| {
+                self.game_players
+                    .iter()
+                    .find(|player| &player.team_name == current)
+                    .map(|player| &player.left_participant)
+            });
         }
+        seat
+    }
 
-        cards
+    pub fn legal_plays(&self) -> CardSet {
+        rules::legal_plays(
+            self.my_current_hand,
+            &self.game_deals,
+            self.in_progress_deal.as_ref(),
+            &self.round_parameters,
+        )
     }
 }
 
@@ -102,7 +142,7 @@ string_enum! {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Clone)]
 pub struct RoundParameters {
     pub round_id: u32,
     pub initiation_phase_in_seconds: u32,
@@ -110,13 +150,76 @@ pub struct RoundParameters {
     pub dealing_phase_in_seconds: u32,
     pub finishing_phase_in_seconds: u32,
     pub number_of_cards_to_be_passed: u32,
+    pub pass_direction: PassDirection,
     pub card_points: BTreeMap<Card, i32>,
 }
 
 impl RoundParameters {
+    /// The number of cards each player passes, none when hands are held.
+    pub fn cards_to_pass(&self) -> usize {
+        match self.pass_direction {
+            PassDirection::Hold => 0,
+            _ => self.number_of_cards_to_be_passed as usize,
+        }
+    }
+
     pub fn points(&self, card: &Card) -> i32 {
         self.card_points.get(card).cloned().unwrap_or_default()
     }
+
+    /// How long the server gives players to pass or to play a card, if it has said.
+    pub fn phase_duration(&self, game_state: &HeartsGameInstanceState) -> Option<Duration> {
+        let seconds = match *game_state {
+            HeartsGameInstanceState::Passing => self.passing_phase_in_seconds,
+            HeartsGameInstanceState::Dealing => self.dealing_phase_in_seconds,
+            _ => 0,
+        };
+        if seconds > 0 {
+            Some(Duration::from_secs(seconds as u64))
+        } else {
+            None
+        }
+    }
+}
+
+string_enum! {
+    PassDirection {
+        Left,
+        Right,
+        Across,
+        Hold,
+    }
+}
+
+impl PassDirection {
+    /// How many seats to the left passed cards go, if they go anywhere.
+    pub fn seats_to_the_left(self, players: usize) -> Option<usize> {
+        match self {
+            PassDirection::Left => Some(1),
+            PassDirection::Across => Some(players / 2),
+            PassDirection::Right => Some(players.saturating_sub(1)),
+            PassDirection::Hold => None,
+        }
+    }
+}
+
+/// The competition server always passes to the right.
+impl Default for PassDirection {
+    fn default() -> PassDirection {
+        PassDirection::Right
+    }
+}
+
+impl fmt::Display for PassDirection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = match *self {
+            PassDirection::Left => "to the left",
+            PassDirection::Right => "to the right",
+            PassDirection::Across => "across",
+            PassDirection::Hold => "nowhere",
+        };
+        write!(f, "{}", direction)
+    }
 }
 
 string_enum! {
@@ -130,7 +233,7 @@ string_enum! {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GameParticipant {
     pub team_name: PlayerName,
     pub left_participant: PlayerName,
@@ -220,4 +323,67 @@ mod tests {
         GameStatus::try_from(game_status_dto).unwrap();
     }
 
+    #[test]
+    fn pass_directions() {
+        let mut game_status_file = File::open("samples/gamestatus2.json").unwrap();
+        let mut game_status_string = String::new();
+        game_status_file
+            .read_to_string(&mut game_status_string)
+            .unwrap();
+        let game_status_dto: GameStatusDto = serde_json::from_str(&game_status_string)
+            .map_err(Error::from)
+            .unwrap();
+        let mut game_status = GameStatus::try_from(game_status_dto).unwrap();
+        let me = PlayerName::new("FlyingBirds");
+        let name = |name: &str| PlayerName::new(name);
+
+        assert_eq!(PassDirection::Right, game_status.pass_direction());
+        assert_eq!(Some(&name("Dummy-1")), game_status.pass_receiver(&me));
+        assert_eq!(Some(&name("Bill")), game_status.pass_giver(&me));
+
+        game_status.round_parameters.pass_direction = PassDirection::Left;
+        assert_eq!(Some(&name("Bill")), game_status.pass_receiver(&me));
+        assert_eq!(Some(&name("Dummy-1")), game_status.pass_giver(&me));
+
+        game_status.round_parameters.pass_direction = PassDirection::Across;
+        assert_eq!(Some(&name("Dummy-0")), game_status.pass_receiver(&me));
+        assert_eq!(Some(&name("Dummy-0")), game_status.pass_giver(&me));
+
+        game_status.round_parameters.pass_direction = PassDirection::Hold;
+        assert_eq!(None, game_status.pass_receiver(&me));
+        assert_eq!(0, game_status.round_parameters.cards_to_pass());
+    }
+
+    #[test]
+    fn hold_when_no_cards_are_passed() {
+        let parameters = |json: &str| {
+            let dto: RoundParametersDto = serde_json::from_str(json).unwrap();
+            RoundParameters::try_from(dto).unwrap()
+        };
+        let held = parameters(
+            r#"{"RoundId": 4, "InitiationPhaseInSeconds": 5, "PassingPhaseInSeconds": 15,
+                "DealingPhaseInSeconds": 5, "FinishingPhaseInSeconds": 5,
+                "NumberOfCardsTobePassed": 0, "CardPoints": []}"#,
+        );
+        assert_eq!(PassDirection::Hold, held.pass_direction);
+
+        let across = parameters(
+            r#"{"RoundId": 3, "InitiationPhaseInSeconds": 5, "PassingPhaseInSeconds": 15,
+                "DealingPhaseInSeconds": 5, "FinishingPhaseInSeconds": 5,
+                "NumberOfCardsTobePassed": 3, "PassDirection": "Across", "CardPoints": []}"#,
+        );
+        assert_eq!(PassDirection::Across, across.pass_direction);
+        assert_eq!(3, across.cards_to_pass());
+    }
+
+    #[test]
+    fn unknown_pass_direction() {
+        let dto: RoundParametersDto = serde_json::from_str(
+            r#"{"RoundId": 2, "InitiationPhaseInSeconds": 5, "PassingPhaseInSeconds": 15,
+                "DealingPhaseInSeconds": 5, "FinishingPhaseInSeconds": 5,
+                "NumberOfCardsTobePassed": 3, "PassDirection": "Sideways", "CardPoints": []}"#,
+        ).unwrap();
+        assert!(RoundParameters::try_from(dto).is_err());
+    }
+
 }
@@ -0,0 +1,387 @@
+use card::Card;
+use card::CardSet;
+use deal::Deal;
+use deal::DealCard;
+use engine;
+use error::Error;
+use error::Result;
+use opponents::Opponents;
+use game_status::GameInstanceState;
+use game_status::GameParticipant;
+use game_status::GameStatus;
+use game_status::HeartsGameInstanceState;
+use game_status::PassDirection;
+use game_status::PlayerName;
+use game_status::RoundState;
+
+use std::str::FromStr;
+
+const DEAL_COUNT: usize = 13;
+
+/// Reads a game state written out by hand, one `key: value` line at a time:
+///
+/// ```text
+/// # Lines starting with # are comments.
+/// players: Derek Williams, Bill, Dummy-0, Dummy-1
+/// me: Derek Williams
+/// round: 3
+/// pass: left
+/// passed: QS 10H 2C
+/// received: 3D 4D 5D
+/// hand: AS 3H 4H ...
+/// deal: Bill 2C, Dummy-0 5C, Dummy-1 KC, Derek Williams AC
+/// deal: Derek Williams 4S
+/// ```
+///
+/// Players are listed in seat order, each one's left participant being the next, and `me`
+/// defaults to the first of them. The round's card points are the competition server's for
+/// the round number. Deals list who played which card in order; the last one is still being
+/// played if it has fewer cards than there are players. Whose turn it is follows from the
+/// deals and the two of clubs, or can be given with `turn`.
+impl FromStr for GameStatus {
+    type Err = Error;
+
+    fn from_str(text: &str) -> Result<GameStatus> {
+        let mut players = Vec::new();
+        let mut me = None;
+        let mut round_parameters = engine::round_parameters(1);
+        let mut pass_direction = None;
+        let mut passed = CardSet::new();
+        let mut received = CardSet::new();
+        let mut hand = CardSet::new();
+        let mut deals = Vec::new();
+        let mut turn = None;
+
+        for line in text.lines().map(|line| line.trim()) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, ':');
+            let key = parts.next().unwrap().trim();
+            let value = match parts.next() {
+                Some(value) => value.trim(),
+                None => return Err(Error::parsing("scenario line", line)),
+            };
+            match key {
+                "players" => players = value.split(',').map(player_name).collect(),
+                "me" => me = Some(PlayerName::new(value)),
+                "round" => {
+                    let round_id = value
+                        .parse()
+                        .map_err(|_| Error::parsing("round", value))?;
+                    round_parameters = engine::round_parameters(round_id);
+                }
+                "pass" => pass_direction = Some(PassDirection::from_str(&capitalize(value))?),
+                "passed" => passed = cards(value)?,
+                "received" => received = cards(value)?,
+                "hand" => hand = cards(value)?,
+                "deal" => deals.push(deal_cards(value)?),
+                "turn" => turn = Some(PlayerName::new(value)),
+                _ => return Err(Error::parsing("scenario line", line)),
+            }
+        }
+
+        if players.len() != engine::PLAYER_COUNT {
+            return Err(Error::Game(
+                format!("{} players are needed", engine::PLAYER_COUNT),
+            ));
+        }
+        let me = me.unwrap_or_else(|| players[0].clone());
+        if !players.contains(&me) {
+            return Err(Error::Game(format!("{} is not playing", me)));
+        }
+        for deal_cards in &deals {
+            for deal_card in deal_cards {
+                if !players.contains(&deal_card.player_name) {
+                    return Err(Error::Game(format!("{} is not playing", deal_card.player_name)));
+                }
+            }
+        }
+        round_parameters.pass_direction = pass_direction.unwrap_or_else(|| {
+            if passed.is_empty() && !deals.is_empty() {
+                PassDirection::Hold
+            } else {
+                PassDirection::Right
+            }
+        });
+
+        let mut game_deals = deals
+            .into_iter()
+            .enumerate()
+            .map(|(index, deal_cards)| {
+                let complete = deal_cards.len() == players.len();
+                let suit = deal_cards.first().map(|deal_card| deal_card.card.suit);
+                Deal {
+                    deal_number: index as u32 + 1,
+                    initiator: deal_cards.first().map(|deal_card| deal_card.player_name.clone()),
+                    suit: suit,
+                    deal_winner: if complete {
+                        deal_cards
+                            .iter()
+                            .filter(|deal_card| Some(deal_card.card.suit) == suit)
+                            .max_by_key(|deal_card| deal_card.card)
+                            .map(|deal_card| deal_card.player_name.clone())
+                    } else {
+                        None
+                    },
+                    deal_cards: deal_cards,
+                }
+            })
+            .collect::<Vec<_>>();
+        if game_deals.iter().rev().skip(1).any(|deal| deal.deal_winner.is_none()) {
+            return Err(Error::Game("Only the last deal can be unfinished".to_owned()));
+        }
+        let dealing = !game_deals.is_empty() || !passed.is_empty() ||
+            round_parameters.cards_to_pass() == 0;
+        let in_progress_deal = match game_deals.last().map(|deal| deal.deal_winner.is_none()) {
+            Some(true) => game_deals.pop(),
+            _ if dealing && game_deals.len() < DEAL_COUNT => {
+                Some(Deal {
+                    deal_number: game_deals.len() as u32 + 1,
+                    initiator: game_deals.last().and_then(|deal| deal.deal_winner.clone()),
+                    suit: None,
+                    deal_cards: Vec::new(),
+                    deal_winner: None,
+                })
+            }
+            _ => None,
+        };
+
+        let played = |player_name: &PlayerName| {
+            game_deals
+                .iter()
+                .chain(in_progress_deal.iter())
+                .flat_map(|deal| deal.deal_cards.iter())
+                .filter(|deal_card| &deal_card.player_name == player_name)
+                .map(|deal_card| deal_card.card)
+                .collect::<CardSet>()
+        };
+        let left_of = |player_name: &PlayerName| {
+            let index = players.iter().position(|other| other == player_name).unwrap();
+            players[(index + 1) % players.len()].clone()
+        };
+        let turn = turn.or_else(|| match in_progress_deal {
+            Some(ref deal) => {
+                match deal.deal_cards.last() {
+                    Some(deal_card) => Some(left_of(&deal_card.player_name)),
+                    None if deal.initiator.is_some() => deal.initiator.clone(),
+                    None if hand.contains(&Card::from_str("2C").unwrap()) => Some(me.clone()),
+                    None => None,
+                }
+            }
+            None => None,
+        });
+
+        let my_final_hand = hand.union(played(&me));
+        let my_initial_hand = my_final_hand.difference(received).union(passed);
+        let game_players = players
+            .iter()
+            .map(|player_name| {
+                GameParticipant {
+                    team_name: player_name.clone(),
+                    left_participant: left_of(player_name),
+                    number_of_cards_in_hand: (DEAL_COUNT - played(player_name).len()) as u32,
+                    has_turn: turn.as_ref() == Some(player_name),
+                    current_score: 0,
+                }
+            })
+            .collect();
+
+        Ok(GameStatus {
+            current_game_id: "scenario".to_owned(),
+            current_game_state: GameInstanceState::Running,
+            current_round_id: round_parameters.round_id,
+            current_round_state: RoundState::Running,
+            game_state: if dealing {
+                HeartsGameInstanceState::Dealing
+            } else {
+                HeartsGameInstanceState::Passing
+            },
+            game_state_description: String::new(),
+            round_parameters: round_parameters,
+            game_players: game_players,
+            my_initial_hand: my_initial_hand,
+            cards_passed_by_me: passed,
+            cards_passed_to_me: received,
+            my_final_hand: if dealing { my_final_hand } else { CardSet::new() },
+            my_current_hand: hand,
+            game_deals: game_deals,
+            in_progress_deal: in_progress_deal,
+            is_my_turn: turn.as_ref() == Some(&me),
+            opponents: Opponents::default(),
+        })
+    }
+}
+
+impl GameStatus {
+    /// Writes the game state as `from_str` reads it, seen by the given player, so that a
+    /// situation met in play can be kept as a scenario.
+    pub fn to_scenario(&self, me: &PlayerName) -> String {
+        let mut lines = Vec::new();
+        let players = self.game_players
+            .iter()
+            .map(|player| format!("{}", player.team_name))
+            .collect::<Vec<_>>();
+        lines.push(format!("players: {}", players.join(", ")));
+        lines.push(format!("me: {}", me));
+        lines.push(format!("round: {}", self.current_round_id));
+        let pass_direction: &str = self.pass_direction().into();
+        lines.push(format!("pass: {}", pass_direction.to_lowercase()));
+        if !self.cards_passed_by_me.is_empty() {
+            lines.push(format!("passed: {}", codes(self.cards_passed_by_me)));
+        }
+        if !self.cards_passed_to_me.is_empty() {
+            lines.push(format!("received: {}", codes(self.cards_passed_to_me)));
+        }
+        lines.push(format!("hand: {}", codes(self.my_current_hand)));
+        for deal in self.game_deals.iter().chain(self.in_progress_deal.iter()) {
+            if !deal.deal_cards.is_empty() {
+                let plays = deal.deal_cards
+                    .iter()
+                    .map(|deal_card| {
+                        format!("{} {}", deal_card.player_name, deal_card.card.code())
+                    })
+                    .collect::<Vec<_>>();
+                lines.push(format!("deal: {}", plays.join(", ")));
+            }
+        }
+        lines.push(String::new());
+        lines.join("\n")
+    }
+}
+
+fn codes(cards: CardSet) -> String {
+    cards.iter().map(|card| card.code()).collect::<Vec<_>>().join(" ")
+}
+
+fn player_name(name: &str) -> PlayerName {
+    PlayerName::new(name.trim())
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars.flat_map(|c| c.to_lowercase())).collect(),
+        None => String::new(),
+    }
+}
+
+fn cards(value: &str) -> Result<CardSet> {
+    value.split_whitespace().map(Card::from_str).collect()
+}
+
+/// Cards played in a deal, as in `Bill 2C, Dummy-0 5C`.
+fn deal_cards(value: &str) -> Result<Vec<DealCard>> {
+    value
+        .split(',')
+        .map(|play| {
+            let play = play.trim();
+            match play.rfind(char::is_whitespace) {
+                Some(index) => {
+                    Card::from_str(&play[index..]).map(|card| {
+                        DealCard {
+                            player_name: player_name(&play[..index]),
+                            card: card,
+                        }
+                    })
+                }
+                None => Err(Error::parsing("deal card", play)),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::Rank::*;
+    use card::Suit::*;
+
+    #[test]
+    fn passing() {
+        let game_status = GameStatus::from_str(
+            "players: North, East, South, West
+             hand: 2C 3C 4C 5C 6C 7C 8C 9C TC JC QC KC AC",
+        ).unwrap();
+
+        assert_eq!(HeartsGameInstanceState::Passing, game_status.game_state);
+        assert_eq!(PassDirection::Right, game_status.pass_direction());
+        assert_eq!(13, game_status.my_current_hand.len());
+        assert!(!game_status.is_my_turn);
+    }
+
+    #[test]
+    fn dealing() {
+        let game_status = GameStatus::from_str(
+            "# East took the first deal.
+             players: North, East, South, West
+             me: South
+             round: 3
+             passed: QS 10H 9C
+             received: 3D 4D 5D
+             hand: 3D 4D 5D 6H 7H 8H 9H 2S 3S 4S 5S 6S
+             deal: North 2C, East 5C, South 4C, West 3C
+             deal: East AD",
+        ).unwrap();
+
+        assert_eq!(HeartsGameInstanceState::Dealing, game_status.game_state);
+        assert_eq!(-1, game_status.round_parameters.points(&Ace.of(Diamond)));
+        assert_eq!(1, game_status.game_deals.len());
+        assert_eq!(
+            Some(&PlayerName::new("East")),
+            game_status.game_deals[0].deal_winner.as_ref()
+        );
+        assert_eq!(1, game_status.in_progress_deal.as_ref().unwrap().deal_cards.len());
+        assert!(game_status.is_my_turn);
+        assert_eq!(12, game_status.game_players[2].number_of_cards_in_hand);
+        assert!(game_status.my_initial_hand.contains(&Queen.of(Spade)));
+        assert!(!game_status.my_initial_hand.contains(&Three.of(Diamond)));
+        assert_eq!(13, game_status.my_initial_hand.len());
+        let legal_plays = game_status.legal_plays().into_iter().collect::<Vec<_>>();
+        assert_eq!(
+            vec![Three.of(Diamond), Four.of(Diamond), Five.of(Diamond)],
+            legal_plays
+        );
+    }
+
+    #[test]
+    fn writes_scenarios_back() {
+        let game_status = GameStatus::from_str(
+            "players: North, East, South, West
+             me: South
+             round: 3
+             passed: QS 10H 9C
+             received: 3D 4D 5D
+             hand: 3D 4D 5D 6H 7H 8H 9H 2S 3S 4S 5S 6S
+             deal: North 2C, East 5C, South 4C, West 3C
+             deal: East AD",
+        ).unwrap();
+        let text = game_status.to_scenario(&PlayerName::new("South"));
+        let written = GameStatus::from_str(&text).unwrap();
+
+        assert!(text.contains("passed: QS TH 9C"));
+        assert!(text.contains("deal: East AD"));
+        assert_eq!(game_status.my_initial_hand, written.my_initial_hand);
+        assert_eq!(game_status.my_current_hand, written.my_current_hand);
+        assert_eq!(game_status.game_deals, written.game_deals);
+        assert_eq!(game_status.in_progress_deal, written.in_progress_deal);
+        assert_eq!(game_status.pass_direction(), written.pass_direction());
+        assert_eq!(game_status.legal_plays(), written.legal_plays());
+        assert!(written.is_my_turn);
+    }
+
+    #[test]
+    fn invalid_scenarios() {
+        assert!(GameStatus::from_str("players: North, East").is_err());
+        assert!(GameStatus::from_str("players: North, East, South, West\nhand: 1S").is_err());
+        assert!(GameStatus::from_str("players: North, East, South, West\nme: Bill").is_err());
+        assert!(GameStatus::from_str("players: North, East, South, West\nscore: 3").is_err());
+        assert!(
+            GameStatus::from_str(
+                "players: North, East, South, West
+                 deal: North 3C, East 5C
+                 deal: South 4C",
+            ).is_err()
+        );
+    }
+}
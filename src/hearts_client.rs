@@ -1,17 +1,34 @@
+use archive::Archive;
 use strategy::CardStrategy;
+use strategy::Decision;
 use game_status::{GameStatus, GameInstanceState, RoundState, HeartsGameInstanceState};
+use game_status::PlayerName;
 use game_status::dto::GameStatusDto;
+use opponents;
+use opponents::Opponents;
+use card::Card;
+use card::CardSet;
 use card::dto::CardDto;
 use error::Error;
 use error::Result;
+use retry::RetryPolicy;
+use rules;
 
 use std::convert::TryFrom;
+use std::fmt;
 use std::fs;
 use std::fs::File;
+use std::fs::OpenOptions;
 use std::io::Read;
 use std::io::Write;
+use std::path::PathBuf;
+use std::collections::BTreeMap;
 use std::collections::BTreeSet;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
 use std::time::Duration;
+use std::time::Instant;
 use std::thread;
 
 use hyper;
@@ -21,7 +38,13 @@ use hyper::header;
 
 use serde_json;
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+/// Time kept back from each phase for submitting the decision.
+const SUBMIT_MARGIN_MILLIS: u64 = 1500;
+/// The least time a strategy is given to decide, even when the phase is short or unknown.
+const MIN_DECISION_MILLIS: u64 = 500;
+
+/// A player's password, which is kept out of `Debug` output and so out of the logs.
+#[derive(Deserialize, PartialEq, Eq, Clone)]
 pub struct Password(String);
 
 impl Password {
@@ -33,6 +56,12 @@ impl Password {
     }
 }
 
+impl fmt::Debug for Password {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Password(***)")
+    }
+}
+
 impl From<Password> for String {
     fn from(password: Password) -> String {
         let Password(string) = password;
@@ -40,49 +69,163 @@ impl From<Password> for String {
     }
 }
 
-#[derive(Deserialize, Debug)]
-struct GameResponse {
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GameResponse {
     fault: Option<String>,
     #[serde(rename = "hasError")]
     has_error: bool,
     data: String,
 }
 
+impl GameResponse {
+    pub fn data<A>(data: A) -> GameResponse
+    where
+        A: Into<String>,
+    {
+        GameResponse {
+            fault: None,
+            has_error: false,
+            data: data.into(),
+        }
+    }
+
+    pub fn fault<A>(fault: A) -> GameResponse
+    where
+        A: Into<String>,
+    {
+        GameResponse {
+            fault: Some(fault.into()),
+            has_error: true,
+            data: String::new(),
+        }
+    }
+}
+
+/// What a client saw of its games, reported once it stops playing.
+#[derive(Debug, Default, Clone)]
+pub struct ClientSummary {
+    pub finished: Vec<FinishedGame>,
+    pub cancelled: u32,
+    pub errors: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct FinishedGame {
+    pub score: i32,
+    /// 1 for the lowest score, ties sharing the better place.
+    pub place: usize,
+}
+
+/// One line of the decision log: the cards a pass or play could have been made with, those it
+/// was made with and, if the strategy said, why. Cards are written as they are displayed.
+#[derive(Serialize, Debug)]
+struct DecisionRecord<'a> {
+    game_id: &'a str,
+    round_id: u32,
+    deal_number: u32,
+    player_name: &'a PlayerName,
+    action: &'static str,
+    legal_cards: Vec<String>,
+    chosen: Vec<String>,
+    /// False when the strategy overran or chose a card it could not play, and the client
+    /// chose instead.
+    by_strategy: bool,
+    decision: Option<Decision>,
+}
+
+/// A decision asked of the strategy worker, which drops it if it only gets to it once the
+/// deadline has passed.
+struct Job<A> {
+    deadline: Instant,
+    decide: Box<FnOnce(&mut A) + Send>,
+}
+
 pub struct HeartsClient<A: CardStrategy> {
+    player_name: PlayerName,
     password: Password,
     base_url: String,
-    card_strategy: A,
+    strategy_jobs: mpsc::Sender<Job<A>>,
+    retry_policy: RetryPolicy,
     player_activity_tracker: BTreeSet<String>,
+    /// When each pass or turn of ours began, as near as the status polls tell.
+    phase_starts: BTreeMap<String, Instant>,
+    /// When the status poll before the current one was sent.
+    previous_poll: Instant,
     client: Client,
     running: bool,
     repeat: bool,
+    /// Set when the client is to stop once it is between games.
+    stop: Option<&'static AtomicBool>,
     current_game_id: Option<String>,
+    log_dir: PathBuf,
+    archive: bool,
+    opponents: Opponents,
+    summary: ClientSummary,
 }
 
-impl<A: CardStrategy> HeartsClient<A> {
+impl<A: CardStrategy + 'static> HeartsClient<A> {
     pub fn new(
         password: Password,
         hostname: &str,
         card_strategy: A,
+        retry_policy: RetryPolicy,
         repeat: bool,
     ) -> HeartsClient<A> {
         let base_url = format!("http://{}/api/participant", hostname);
         HeartsClient {
+            player_name: card_strategy.player_name().clone(),
             password: password,
             base_url: base_url,
-            card_strategy: card_strategy,
+            strategy_jobs: Self::start_strategy(card_strategy),
+            retry_policy: retry_policy,
             player_activity_tracker: BTreeSet::new(),
+            phase_starts: BTreeMap::new(),
+            previous_poll: Instant::now(),
             client: Client::new(),
             running: false,
             repeat: repeat,
+            stop: None,
             current_game_id: None,
+            log_dir: PathBuf::from("game_log"),
+            archive: false,
+            opponents: Opponents::default(),
+            summary: ClientSummary::default(),
         }
     }
 
-    pub fn play(mut self) {
+    /// Logs each game to its own directory under `log_dir`, `game_log` unless set.
+    pub fn log_to<P: Into<PathBuf>>(mut self, log_dir: P) -> HeartsClient<A> {
+        self.log_dir = log_dir.into();
+        self
+    }
+
+    /// Logs each game to a compressed archive in the log directory, and indexes the games
+    /// there, rather than logging them to directories of snapshots.
+    pub fn archive(mut self, archive: bool) -> HeartsClient<A> {
+        self.archive = archive;
+        self
+    }
+
+    /// Stops the client between games once `stop` is set, even if it was to play on.
+    pub fn stop_on(mut self, stop: &'static AtomicBool) -> HeartsClient<A> {
+        self.stop = Some(stop);
+        self
+    }
+
+    fn stop_requested(&self) -> bool {
+        self.stop.map_or(false, |stop| stop.load(Ordering::SeqCst))
+    }
+
+    pub fn play(mut self) -> ClientSummary {
         self.running = true;
+        self.opponents = Opponents::load(self.opponents_file()).unwrap_or_else(|e| {
+            warn!("Could not read the opponents, starting afresh: {}", e);
+            Opponents::default()
+        });
         self.check_server_connectivity();
+        self.previous_poll = Instant::now();
         while self.running {
+            let poll = Instant::now();
             self.get_game_status()
                 .and_then(|game_status| {
                     self.set_current_game_id(&game_status.current_game_id);
@@ -90,15 +233,20 @@ impl<A: CardStrategy> HeartsClient<A> {
                     self.update_game_state(state);
                     match *state {
                         GameInstanceState::Open => self.on_game_open(),
-                        GameInstanceState::Finished => self.on_game_finished(),
-                        GameInstanceState::Cancelled => self.on_game_finished(),
+                        GameInstanceState::Finished => self.on_game_finished(&game_status),
+                        GameInstanceState::Cancelled => self.on_game_finished(&game_status),
                         GameInstanceState::Running => self.on_game_running(&game_status),
                         _ => Ok(()),
                     }
                 })
-                .unwrap_or_else(|e| error!("Unexpected failure: {}", e));
+                .unwrap_or_else(|e| {
+                    error!("Unexpected failure: {}", e);
+                    self.summary.errors += 1;
+                });
+            self.previous_poll = poll;
             thread::sleep(Duration::new(1, 0));
         }
+        self.summary
     }
 
     fn set_current_game_id(&mut self, game_id: &str) {
@@ -110,6 +258,7 @@ impl<A: CardStrategy> HeartsClient<A> {
         )
         {
             self.player_activity_tracker.clear();
+            self.phase_starts.clear();
             self.current_game_id = Some(game_id.to_owned())
         }
     }
@@ -127,22 +276,63 @@ impl<A: CardStrategy> HeartsClient<A> {
 
     fn on_game_open(&mut self) -> Result<()> {
         let key_join_status = "JoinGame".to_owned();
-        if !self.player_activity_tracker.contains(&key_join_status) && self.join_game() {
+        if !self.player_activity_tracker.contains(&key_join_status) {
+            if self.stop_requested() {
+                info!("Stopping before joining another game");
+                self.running = false;
+                return Ok(());
+            }
+            self.join_game()?;
             info!("Join successful");
             self.player_activity_tracker.insert(key_join_status);
         };
         Ok(())
     }
 
-    fn on_game_finished(&mut self) -> Result<()> {
+    fn on_game_finished(&mut self, game_status: &GameStatus) -> Result<()> {
+        let key_summary = "Summary".to_owned();
+        if !self.player_activity_tracker.contains(&key_summary) {
+            if game_status.current_round_id > 0 {
+                self.learn(game_status);
+            }
+            self.record_summary(game_status);
+            self.player_activity_tracker.insert(key_summary);
+            if self.archive {
+                Archive::new(self.log_dir.clone()).index_game(game_status)?;
+            }
+        }
         if !self.repeat {
             self.running = false;
+        } else if self.stop_requested() {
+            info!("Stopping after the game");
+            self.running = false;
         }
         Ok(())
     }
 
+    fn record_summary(&mut self, game_status: &GameStatus) {
+        if game_status.current_game_state == GameInstanceState::Cancelled {
+            self.summary.cancelled += 1;
+            return;
+        }
+        let players = &game_status.game_players;
+        if let Some(me) = players.iter().find(|player| player.team_name == self.player_name) {
+            let place = 1 +
+                players
+                    .iter()
+                    .filter(|player| player.current_score < me.current_score)
+                    .count();
+            info!("Game finished with {} points, in place {}", me.current_score, place);
+            self.summary.finished.push(FinishedGame {
+                score: me.current_score,
+                place: place,
+            });
+        }
+    }
+
     fn on_game_running(&mut self, game_status: &GameStatus) -> Result<()> {
         if game_status.current_round_id > 0 {
+            self.learn(game_status);
             self.update_activity_tracker(format!(
                 "Round {} - {:?}",
                 game_status.current_round_id,
@@ -166,6 +356,24 @@ impl<A: CardStrategy> HeartsClient<A> {
             game_status.game_state
         ));
 
+        if game_status.game_state == HeartsGameInstanceState::Dealing &&
+            !game_status.cards_passed_to_me.is_empty()
+        {
+            if let Some(giver) = game_status.pass_giver(&self.player_name) {
+                self.update_activity_tracker(format!(
+                    "Round {} - Cards passed to me by {}: {}",
+                    game_status.current_round_id,
+                    giver,
+                    game_status
+                        .cards_passed_to_me
+                        .iter()
+                        .map(|card| format!("{}", card))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ));
+            }
+        }
+
         match game_status.game_state {
             HeartsGameInstanceState::Passing => self.on_passing(game_status),
             HeartsGameInstanceState::Dealing => self.on_dealing(game_status),
@@ -176,8 +384,9 @@ impl<A: CardStrategy> HeartsClient<A> {
     fn on_passing(&mut self, game_status: &GameStatus) -> Result<()> {
         let key_passing = format!("Passing - Round {}", game_status.current_round_id);
         if !self.player_activity_tracker.contains(&key_passing) {
+            let deadline = Self::deadline(game_status, self.phase_start(&key_passing));
             self.log_game_status(game_status, 0)?;
-            self.do_passing_activity(game_status)?;
+            self.do_passing_activity(game_status, deadline)?;
             self.player_activity_tracker.insert(key_passing);
         }
         Ok(())
@@ -196,20 +405,73 @@ impl<A: CardStrategy> HeartsClient<A> {
                 deal_number
             );
             if !self.player_activity_tracker.contains(&key_dealing) {
+                let deadline = Self::deadline(game_status, self.phase_start(&key_dealing));
                 self.log_game_status(game_status, deal_number)?;
-                self.do_dealing_activity(game_status)?;
+                self.do_dealing_activity(game_status, deadline)?;
                 self.player_activity_tracker.insert(key_dealing);
             }
         }
         Ok(())
     }
 
+    fn opponents_file(&self) -> PathBuf {
+        self.log_dir.join(opponents::FILE)
+    }
+
+    /// Learns from the deals finished since the last status, the cards passed to us, and once
+    /// the round is over who tried to shoot the moon, saving the model after each round.
+    fn learn(&mut self, game_status: &GameStatus) {
+        let round_id = game_status.current_round_id;
+        for deal in &game_status.game_deals {
+            let key_deal = format!("Learned - Round {} Deal {}", round_id, deal.deal_number);
+            if !self.player_activity_tracker.contains(&key_deal) {
+                self.opponents.learn_deal(deal, &self.player_name);
+                self.player_activity_tracker.insert(key_deal);
+            }
+        }
+
+        let key_pass = format!("Learned - Round {} Pass", round_id);
+        if !game_status.cards_passed_to_me.is_empty() &&
+            !self.player_activity_tracker.contains(&key_pass)
+        {
+            if let Some(giver) = game_status.pass_giver(&self.player_name) {
+                self.opponents.learn_pass(giver, game_status.cards_passed_to_me);
+            }
+            self.player_activity_tracker.insert(key_pass);
+        }
+
+        let key_round = format!("Learned - Round {}", round_id);
+        if game_status.current_round_state == RoundState::Finished &&
+            !self.player_activity_tracker.contains(&key_round)
+        {
+            self.opponents.learn_round(
+                &game_status.game_players,
+                &game_status.game_deals,
+                &self.player_name,
+            );
+            self.player_activity_tracker.insert(key_round);
+            if let Err(e) = self.opponents.save(self.opponents_file()) {
+                warn!("Could not save the opponents: {}", e);
+            }
+        }
+    }
+
+    /// The game status as the strategy sees it, with what is known of the opponents.
+    fn with_opponents(&self, game_status: &GameStatus) -> GameStatus {
+        let mut status = game_status.clone();
+        status.opponents = self.opponents.at_table(&game_status.game_players, &self.player_name);
+        status
+    }
+
     fn log_game_status(&self, game_status: &GameStatus, deal_number: u32) -> Result<()> {
+        if self.archive {
+            return Archive::new(self.log_dir.clone()).append(game_status, deal_number);
+        }
         let game_id = &game_status.current_game_id;
         let round_id = game_status.current_round_id;
-        let dir_name = format!("game_log/{}", game_id);
+        let dir_name = self.log_dir.join(game_id);
         fs::DirBuilder::new().recursive(true).create(&dir_name)?;
-        let file_name = format!("{}/{:02}-{:02}.json", dir_name, round_id, deal_number);
+        let file_name = dir_name.join(format!("{:02}-{:02}.json", round_id, deal_number));
         let mut file = File::create(file_name)?;
         let dto = GameStatusDto::from(game_status);
         let string = serde_json::to_string_pretty(&dto)?;
@@ -218,29 +480,185 @@ impl<A: CardStrategy> HeartsClient<A> {
         Ok(())
     }
 
-    fn do_passing_activity(&mut self, game_status: &GameStatus) -> Result<()> {
-        let number_of_cards_to_be_passed =
-            game_status.round_parameters.number_of_cards_to_be_passed;
+    /// Appends the decision to `decisions.jsonl` in the game's log directory or, when games are
+    /// archived, to the game's archived decisions. `decision` is `None` when the client chose
+    /// instead of the strategy. A decision that cannot be logged is still submitted.
+    fn log_decision(
+        &self,
+        game_status: &GameStatus,
+        action: &'static str,
+        legal_cards: CardSet,
+        chosen: &[Card],
+        decision: Option<Option<Decision>>,
+    ) {
+        let record = DecisionRecord {
+            game_id: &game_status.current_game_id,
+            round_id: game_status.current_round_id,
+            deal_number: match game_status.game_state {
+                HeartsGameInstanceState::Dealing => {
+                    game_status
+                        .in_progress_deal
+                        .as_ref()
+                        .map(|deal| deal.deal_number)
+                        .unwrap_or_default()
+                }
+                _ => 0,
+            },
+            player_name: &self.player_name,
+            action: action,
+            legal_cards: legal_cards.iter().map(|card| format!("{}", card)).collect(),
+            chosen: chosen.iter().map(|card| format!("{}", card)).collect(),
+            by_strategy: decision.is_some(),
+            decision: decision.and_then(|decision| decision),
+        };
+        let logged = serde_json::to_string(&record)
+            .map_err(Error::from)
+            .and_then(|mut line| {
+                line.push('\n');
+                if self.archive {
+                    return Archive::new(self.log_dir.clone())
+                        .append_decision(&game_status.current_game_id, &line);
+                }
+                let dir_name = self.log_dir.join(&game_status.current_game_id);
+                fs::DirBuilder::new().recursive(true).create(&dir_name)?;
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(dir_name.join("decisions.jsonl"))?;
+                file.write_all(line.as_bytes())?;
+                Ok(())
+            });
+        if let Err(e) = logged {
+            warn!("Could not log the decision: {}", e);
+        }
+    }
+
+    /// When the pass or turn was first seen to have begun. It began after the status poll
+    /// before the one that showed it was sent, so that is taken as its start.
+    fn phase_start(&mut self, key: &str) -> Instant {
+        *self.phase_starts.entry(key.to_owned()).or_insert(self.previous_poll)
+    }
+
+    /// The time by which a decision is needed for it to be submitted before the phase that
+    /// started at `started` is over.
+    fn deadline(game_status: &GameStatus, started: Instant) -> Instant {
+        let margin = Duration::from_millis(SUBMIT_MARGIN_MILLIS);
+        let least = Instant::now() + Duration::from_millis(MIN_DECISION_MILLIS);
+        game_status
+            .round_parameters
+            .phase_duration(&game_status.game_state)
+            .and_then(|phase| phase.checked_sub(margin))
+            .map_or(least, |budget| (started + budget).max(least))
+    }
+
+    /// Runs the strategy in a thread of its own for as long as the client is around. A
+    /// decision that overruns only holds up the ones after it, which are dropped rather than
+    /// made for a turn that has passed.
+    fn start_strategy(mut card_strategy: A) -> mpsc::Sender<Job<A>> {
+        let (sender, receiver) = mpsc::channel::<Job<A>>();
+        let name = thread::current().name().map(|name| name.to_owned());
+        let worker = match name {
+            Some(name) => thread::Builder::new().name(name),
+            None => thread::Builder::new(),
+        };
+        let spawned = worker.spawn(move || {
+            for job in receiver {
+                if Instant::now() < job.deadline {
+                    (job.decide)(&mut card_strategy);
+                } else {
+                    warn!("Dropping a decision the strategy was too late for");
+                }
+            }
+        });
+        if let Err(e) = spawned {
+            warn!("Could not start the strategy: {}", e);
+        }
+        sender
+    }
+
+    /// Asks the strategy worker for a decision, giving up on it at the deadline.
+    fn decide<T, F>(&self, deadline: Instant, decision: F) -> Option<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut A) -> T + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel();
+        let job = Job {
+            deadline: deadline,
+            decide: Box::new(move |card_strategy: &mut A| {
+                let _ = sender.send(decision(card_strategy));
+            }),
+        };
+        if self.strategy_jobs.send(job).is_err() {
+            warn!("The strategy has stopped");
+            return None;
+        }
+        let now = Instant::now();
+        let budget = if deadline > now {
+            deadline - now
+        } else {
+            Duration::from_millis(0)
+        };
+        receiver.recv_timeout(budget).ok()
+    }
+
+    fn do_passing_activity(&mut self, game_status: &GameStatus, deadline: Instant) -> Result<()> {
+        let number_of_cards_to_be_passed = game_status.round_parameters.cards_to_pass();
+        let receiver = match game_status.pass_receiver(&self.player_name) {
+            Some(receiver) if number_of_cards_to_be_passed > 0 => receiver,
+            _ => {
+                info!("No cards are passed this round.");
+                return Ok(());
+            }
+        };
         info!(
-            "{} cards need to be passed to the right.",
-            number_of_cards_to_be_passed
+            "{} cards need to be passed {}, to {}.",
+            number_of_cards_to_be_passed,
+            game_status.pass_direction(),
+            receiver
+        );
+        let status = self.with_opponents(game_status);
+        let decision = self.decide(deadline, move |strategy| {
+            let cards = strategy.pass_cards(&status);
+            (cards, strategy.take_decision())
+        });
+        let (cards_to_pass, decision) = match decision {
+            Some((cards, decision)) => (cards, Some(decision)),
+            None => {
+                let cards = rules::fallback_pass(
+                    game_status.my_initial_hand,
+                    &game_status.round_parameters,
+                );
+                warn!("Strategy did not pass in time, passing the riskiest cards instead");
+                (cards, None)
+            }
+        };
+        self.log_decision(
+            game_status,
+            "pass",
+            game_status.my_initial_hand,
+            &cards_to_pass,
+            decision,
         );
-        let cards_to_pass = self.card_strategy.pass_cards(game_status);
         let cards_to_pass_dto = cards_to_pass
             .iter()
-            .map(|&card| card.into())
-            .collect::<Vec<CardDto>>();
+            .map(CardDto::from)
+            .collect::<Vec<_>>();
 
         let serialized_cards_to_pass = serde_json::to_string(&cards_to_pass_dto)?;
-
-        self.client
-            .post(&format!("{}/passcards", self.base_url))
-            .header(self.authorization())
-            .header(header::ContentType::json())
-            .body(&serialized_cards_to_pass)
-            .send()
-            .map_err(Error::from)
-            .and_then(Self::parse_game_response)
+        let submit_by = deadline + Duration::from_millis(SUBMIT_MARGIN_MILLIS);
+
+        self.retry_policy
+            .submit("Passing cards", submit_by, || {
+                self.client
+                    .post(&format!("{}/passcards", self.base_url))
+                    .header(self.authorization())
+                    .header(header::ContentType::json())
+                    .body(&serialized_cards_to_pass)
+                    .send()
+                    .map_err(Error::from)
+                    .and_then(Self::parse_game_response)
+            })
             .map(|_| {
                 let passed_cards = cards_to_pass
                     .iter()
@@ -256,54 +674,95 @@ impl<A: CardStrategy> HeartsClient<A> {
             })
     }
 
-    fn do_dealing_activity(&mut self, game_status: &GameStatus) -> Result<()> {
-        let card_to_deal = self.card_strategy.play_card(game_status);
-        let card_to_deal_dto: CardDto = card_to_deal.into();
+    fn do_dealing_activity(&mut self, game_status: &GameStatus, deadline: Instant) -> Result<()> {
+        let legal_plays = game_status.legal_plays();
+        let fallback_card = rules::fallback_play(legal_plays, &game_status.round_parameters)
+            .ok_or_else(|| Error::game(Some("No legal cards to play")))?;
+        let status = self.with_opponents(game_status);
+        let decision = self.decide(deadline, move |strategy| {
+            let card = strategy.play_card(&status);
+            (card, strategy.take_decision())
+        });
+        let (card_to_deal, decision) = match decision {
+            Some((chosen_card, decision)) if legal_plays.contains(&chosen_card) => {
+                (chosen_card, Some(decision))
+            }
+            Some((chosen_card, _)) => {
+                warn!(
+                    "Strategy chose {} which cannot be played, playing {} instead",
+                    chosen_card,
+                    fallback_card
+                );
+                (fallback_card, None)
+            }
+            None => {
+                warn!("Strategy did not choose in time, playing {} instead", fallback_card);
+                (fallback_card, None)
+            }
+        };
+        self.log_decision(game_status, "play", legal_plays, &[card_to_deal], decision);
+        let card_to_deal_dto = CardDto::from(&card_to_deal);
 
         let serialized_card_to_deal = serde_json::to_string(&card_to_deal_dto)?;
-
-        self.client
-            .post(&format!("{}/playcard", self.base_url))
-            .header(self.authorization())
-            .header(header::ContentType::json())
-            .body(&serialized_card_to_deal)
-            .send()
-            .map_err(Error::from)
-            .and_then(Self::parse_game_response)
+        let submit_by = deadline + Duration::from_millis(SUBMIT_MARGIN_MILLIS);
+
+        self.retry_policy
+            .submit("Playing a card", submit_by, || {
+                self.client
+                    .post(&format!("{}/playcard", self.base_url))
+                    .header(self.authorization())
+                    .header(header::ContentType::json())
+                    .body(&serialized_card_to_deal)
+                    .send()
+                    .map_err(Error::from)
+                    .and_then(Self::parse_game_response)
+            })
             .map(|_| info!("{} played Successfully", card_to_deal))
     }
 
     fn check_server_connectivity(&self) {
-        while !self.ping() {
-            info!("Trying to connect to server {}", self.base_url);
+        while let Err(e) = self.ping() {
+            info!("Trying to connect to server {}: {}", self.base_url, e);
             thread::sleep(Duration::new(5, 0));
         }
     }
 
-    fn ping(&self) -> bool {
-        self.client
-            .head(&self.base_url)
-            .header(self.authorization())
-            .send()
-            .is_ok()
+    fn ping(&self) -> Result<()> {
+        self.retry_policy.run("Connecting", || {
+            self.client
+                .head(&self.base_url)
+                .header(self.authorization())
+                .send()
+                .map_err(Error::from)
+                .and_then(Self::check_status)
+                .map(|_| ())
+        })
     }
 
     fn get_game_status(&self) -> Result<GameStatus> {
-        self.client
-            .get(&format!("{}/gamestatus", &self.base_url))
-            .header(self.authorization())
-            .send()
-            .map_err(Error::from)
-            .and_then(Self::parse_game_response)
+        self.retry_policy
+            .run("Getting the game status", || {
+                self.client
+                    .get(&format!("{}/gamestatus", &self.base_url))
+                    .header(self.authorization())
+                    .send()
+                    .map_err(Error::from)
+                    .and_then(Self::parse_game_response)
+            })
             .and_then(Self::parse_game_status)
     }
 
-    fn join_game(&self) -> bool {
-        self.client
-            .post(&format!("{}/join", &self.base_url))
-            .header(self.authorization())
-            .send()
-            .is_ok()
+    fn join_game(&self) -> Result<()> {
+        self.retry_policy
+            .run("Joining", || {
+                self.client
+                    .post(&format!("{}/join", &self.base_url))
+                    .header(self.authorization())
+                    .send()
+                    .map_err(Error::from)
+                    .and_then(Self::parse_game_response)
+            })
+            .map(|_| ())
     }
 
     fn parse_game_status(game_response: String) -> Result<GameStatus> {
@@ -312,8 +771,19 @@ impl<A: CardStrategy> HeartsClient<A> {
             .and_then(GameStatus::try_from)
     }
 
-    fn parse_game_response(mut response: Response) -> Result<String> {
-        assert_eq!(hyper::Ok, response.status);
+    /// The response if the server answered 200 OK, otherwise its status and body as an error.
+    fn check_status(mut response: Response) -> Result<Response> {
+        if response.status == hyper::Ok {
+            Ok(response)
+        } else {
+            let mut response_body = String::new();
+            response.read_to_string(&mut response_body).unwrap_or_default();
+            Err(Error::Status(response.status, response_body))
+        }
+    }
+
+    fn parse_game_response(response: Response) -> Result<String> {
+        let mut response = Self::check_status(response)?;
         let mut response_body = String::new();
         response.read_to_string(&mut response_body)?;
         let game_response: GameResponse = serde_json::from_str(&response_body)?;
@@ -326,8 +796,102 @@ impl<A: CardStrategy> HeartsClient<A> {
 
     fn authorization(&self) -> header::Authorization<header::Basic> {
         header::Authorization(header::Basic {
-            username: self.card_strategy.player_name().clone().into(),
+            username: self.player_name.clone().into(),
             password: Some(self.password.clone().into()),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use scenarios;
+    use strategy::SimpleCardStrategy;
+
+    use std::path::Path;
+    use std::str::FromStr;
+
+    fn client() -> HeartsClient<SimpleCardStrategy> {
+        let strategy = SimpleCardStrategy::new(PlayerName::new("North"));
+        HeartsClient::new(Password::new(""), "localhost", strategy, RetryPolicy::default(), false)
+    }
+
+    #[test]
+    fn drops_decisions_the_strategy_is_too_late_for() {
+        let client = client();
+        let (sender, receiver) = mpsc::channel();
+        let soon = || Instant::now() + Duration::from_millis(50);
+
+        let overrun = client.decide(soon(), |_| thread::sleep(Duration::from_millis(300)));
+        assert_eq!(None, overrun);
+        let stale = client.decide(soon(), move |_| sender.send(()).unwrap());
+        assert_eq!(None, stale);
+
+        thread::sleep(Duration::from_millis(300));
+        let later = Instant::now() + Duration::from_secs(5);
+        assert_eq!(Some(7), client.decide(later, |_| 7));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[test]
+    fn stops_between_games_when_asked() {
+        static STOP: AtomicBool = AtomicBool::new(false);
+        let mut client = client().stop_on(&STOP);
+        client.repeat = true;
+        client.running = true;
+        let mut game_status = GameStatus::from_str(
+            "players: North, East, South, West
+             hand: 2C 3C 4C 5C 6C 7C 8C 9C TC JC QC KC AC",
+        ).unwrap();
+        game_status.current_game_state = GameInstanceState::Finished;
+        game_status.current_round_id = 0;
+
+        client.on_game_finished(&game_status).unwrap();
+        assert!(client.running);
+        STOP.store(true, Ordering::SeqCst);
+        client.on_game_finished(&game_status).unwrap();
+        assert!(!client.running);
+
+        client.running = true;
+        client.player_activity_tracker.clear();
+        client.on_game_open().unwrap();
+        assert!(!client.running);
+    }
+
+    #[test]
+    fn deadlines_count_from_the_start_of_the_phase() {
+        let game_status = GameStatus::from_str(
+            "players: North, East, South, West
+             hand: 2C 3C 4C 5C 6C 7C 8C 9C TC JC QC KC AC",
+        ).unwrap();
+        assert_eq!(15, game_status.round_parameters.passing_phase_in_seconds);
+        let started = Instant::now() - Duration::from_secs(10);
+
+        let deadline = HeartsClient::<SimpleCardStrategy>::deadline(&game_status, started);
+        assert_eq!(started + Duration::from_millis(13_500), deadline);
+
+        let late = Instant::now() - Duration::from_secs(20);
+        let deadline = HeartsClient::<SimpleCardStrategy>::deadline(&game_status, late);
+        assert!(deadline >= Instant::now() + Duration::from_millis(MIN_DECISION_MILLIS - 100));
+    }
+
+    #[test]
+    fn learns_passes_from_the_player_on_our_left() {
+        let game_status = scenarios::read_game_status(Path::new("samples/gamestatus.json"))
+            .unwrap();
+        let me = PlayerName::new("FlyingBirds");
+        let strategy = SimpleCardStrategy::new(me.clone());
+        let retry_policy = RetryPolicy::default();
+        let mut client =
+            HeartsClient::new(Password::new(""), "localhost", strategy, retry_policy, false);
+        client.learn(&game_status);
+
+        let left = PlayerName::new("Dummy-0");
+        let right = PlayerName::new("Dummy-2");
+        assert_eq!(Some(&right), game_status.pass_receiver(&me));
+        let giver = client.opponents.profile(&left).unwrap();
+        assert_eq!(1, giver.passes);
+        assert_eq!(3, giver.cards_passed);
+        assert!(client.opponents.profile(&right).map_or(true, |profile| profile.passes == 0));
+    }
+}
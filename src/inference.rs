@@ -0,0 +1,256 @@
+use card::Card;
+use card::CardSet;
+use card::Suit;
+use deal::Deal;
+use game_status::GameStatus;
+use game_status::PlayerName;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+/// What can be worked out about every player's hand from one player's view of the round:
+/// the cards each of them could still hold and how many cards they have left.
+///
+/// Players cannot hold cards that have been played, cards in our hand or cards of a suit
+/// they did not follow. The cards we passed are with the player we passed them to until
+/// they play them, unless hands are held. Whenever a player could only hold as many cards as
+/// they have left, or is known to hold that many, nobody else can hold those cards either.
+#[derive(Debug)]
+pub struct Inference {
+    hand_sizes: BTreeMap<PlayerName, usize>,
+    possible_cards: BTreeMap<PlayerName, CardSet>,
+}
+
+impl Inference {
+    pub fn new(game_status: &GameStatus, player_name: &PlayerName) -> Inference {
+        let unplayed_cards = game_status.unplayed_cards();
+        let deals = game_status
+            .game_deals
+            .iter()
+            .chain(game_status.in_progress_deal.iter())
+            .collect::<Vec<_>>();
+        let hand_size = Card::all().len() / game_status.game_players.len().max(1);
+        let receiver = game_status.pass_receiver(player_name);
+        let passed_cards = game_status.cards_passed_by_me.intersection(unplayed_cards);
+
+        let mut hand_sizes = BTreeMap::new();
+        let mut possible_cards = BTreeMap::new();
+        for player in &game_status.game_players {
+            let other = &player.team_name;
+            if other == player_name {
+                hand_sizes.insert(other.clone(), game_status.my_current_hand.len());
+                possible_cards.insert(other.clone(), game_status.my_current_hand);
+                continue;
+            }
+            let played = deals
+                .iter()
+                .flat_map(|deal| deal.deal_cards.iter())
+                .filter(|deal_card| &deal_card.player_name == other)
+                .count();
+            let void_cards = Self::void_suits(&deals, other)
+                .into_iter()
+                .fold(CardSet::new(), |cards, suit| cards.union(Card::all().suit(suit)));
+            let cards = if receiver == Some(other) {
+                unplayed_cards.difference(void_cards).union(passed_cards)
+            } else {
+                unplayed_cards.difference(void_cards).difference(passed_cards)
+            };
+            hand_sizes.insert(other.clone(), hand_size.saturating_sub(played));
+            possible_cards.insert(other.clone(), cards);
+        }
+
+        let mut inference = Inference {
+            hand_sizes: hand_sizes,
+            possible_cards: possible_cards,
+        };
+        inference.narrow();
+        inference
+    }
+
+    /// The players who could be holding the card.
+    #[allow(dead_code)]
+    pub fn possible_holders(&self, card: &Card) -> BTreeSet<&PlayerName> {
+        self.possible_cards
+            .iter()
+            .filter(|&(_, cards)| cards.contains(card))
+            .map(|(player_name, _)| player_name)
+            .collect()
+    }
+
+    /// The cards the player is certain to be holding.
+    pub fn known_cards(&self, player_name: &PlayerName) -> CardSet {
+        let others = self.possible_cards
+            .iter()
+            .filter(|&(other, _)| other != player_name)
+            .fold(CardSet::new(), |cards, (_, other_cards)| cards.union(*other_cards));
+        self.possible_cards(player_name).difference(others)
+    }
+
+    /// The cards the player could be holding.
+    pub fn possible_cards(&self, player_name: &PlayerName) -> CardSet {
+        self.possible_cards
+            .get(player_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// The number of cards the player has left.
+    pub fn hand_size(&self, player_name: &PlayerName) -> usize {
+        self.hand_sizes.get(player_name).cloned().unwrap_or_default()
+    }
+
+    /// Suits the player did not follow.
+    fn void_suits(deals: &[&Deal], player_name: &PlayerName) -> BTreeSet<Suit> {
+        deals
+            .iter()
+            .filter_map(|deal| {
+                let suit = deal.suit.or_else(|| {
+                    deal.deal_cards.first().map(|deal_card| deal_card.card.suit)
+                });
+                suit.and_then(|suit| {
+                    deal.deal_cards
+                        .iter()
+                        .filter(|deal_card| {
+                            &deal_card.player_name == player_name && deal_card.card.suit != suit
+                        })
+                        .map(|_| suit)
+                        .next()
+                })
+            })
+            .collect()
+    }
+
+    /// Pins cards down to players until nothing more can be worked out.
+    fn narrow(&mut self) {
+        let mut narrowed = true;
+        while narrowed {
+            narrowed = false;
+            let players = self.possible_cards.keys().cloned().collect::<Vec<_>>();
+            for player_name in &players {
+                let hand_size = self.hand_size(player_name);
+                let possible_cards = self.possible_cards[player_name];
+                let known_cards = self.known_cards(player_name);
+                let held = if possible_cards.len() <= hand_size {
+                    possible_cards
+                } else if known_cards.len() >= hand_size &&
+                           known_cards.len() < possible_cards.len()
+                {
+                    self.possible_cards.insert(player_name.clone(), known_cards);
+                    narrowed = true;
+                    known_cards
+                } else {
+                    continue;
+                };
+                for (other, cards) in &mut self.possible_cards {
+                    if other != player_name && !cards.intersection(held).is_empty() {
+                        *cards = cards.difference(held);
+                        narrowed = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine;
+    use engine::Round;
+    use game_status::GameInstanceState;
+    use scenarios;
+
+    use rand::SeedableRng;
+    use rand::StdRng;
+
+    use std::path::Path;
+
+    fn players() -> Vec<PlayerName> {
+        ["North", "East", "South", "West"]
+            .iter()
+            .map(|name| PlayerName::new(*name))
+            .collect()
+    }
+
+    /// A round with every player passing their lowest cards, played up to the given number
+    /// of cards with everyone playing their highest valid card.
+    fn round(seed: usize, cards_played: usize) -> Round {
+        let mut rng = StdRng::from_seed(&[seed]);
+        let mut round = Round::new(engine::round_parameters(1), players(), &mut rng);
+        round.start_passing();
+        for player_name in players() {
+            let hand = round
+                .game_status("", GameInstanceState::Running, &player_name)
+                .my_current_hand;
+            round
+                .pass_cards(&player_name, hand.into_iter().take(3).collect())
+                .unwrap();
+        }
+        round.exchange_cards();
+        for _ in 0..cards_played {
+            let player_name = round.turn().unwrap().clone();
+            let card = round.valid_plays(&player_name).iter().next_back().unwrap();
+            round.play_card(&player_name, card).unwrap();
+        }
+        round
+    }
+
+    #[test]
+    fn possible_cards_include_actual_hands() {
+        for seed in 0..5 {
+            for &cards_played in &[0, 9, 22, 37, 50] {
+                let round = round(seed, cards_played);
+                let me = &players()[seed % 4];
+                let game_status = round.game_status("", GameInstanceState::Running, me);
+                let inference = Inference::new(&game_status, me);
+
+                for player_name in &players() {
+                    let hand = round
+                        .game_status("", GameInstanceState::Running, player_name)
+                        .my_current_hand;
+                    assert_eq!(hand.len(), inference.hand_size(player_name));
+                    assert!(hand.is_subset(inference.possible_cards(player_name)));
+                    assert!(inference.known_cards(player_name).is_subset(hand));
+                    for card in &hand {
+                        assert!(inference.possible_holders(&card).contains(player_name));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn passed_cards_are_known() {
+        // FlyingBirds passed ♣7 ♦9 ♣4 to Dummy-2 on their right, who has since played ♣7.
+        let game_status = scenarios::read_game_status(Path::new("samples/gamestatus.json"))
+            .unwrap();
+        let me = PlayerName::new("FlyingBirds");
+        let inference = Inference::new(&game_status, &me);
+
+        let right = PlayerName::new("Dummy-2");
+        let passed = game_status
+            .cards_passed_by_me
+            .intersection(game_status.unplayed_cards());
+        assert_eq!(2, passed.len());
+        assert!(passed.is_subset(inference.known_cards(&right)));
+        for card in &passed {
+            let holders = inference.possible_holders(&card);
+            assert_eq!(vec![&right], holders.into_iter().collect::<Vec<_>>());
+        }
+        assert_eq!(game_status.my_current_hand, inference.known_cards(&me));
+    }
+
+    #[test]
+    fn played_cards_have_no_holders() {
+        let round = round(2, 20);
+        let me = PlayerName::new("East");
+        let game_status = round.game_status("", GameInstanceState::Running, &me);
+        let inference = Inference::new(&game_status, &me);
+
+        for deal in &game_status.game_deals {
+            for deal_card in &deal.deal_cards {
+                assert!(inference.possible_holders(&deal_card.card).is_empty());
+            }
+        }
+    }
+}
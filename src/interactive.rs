@@ -0,0 +1,439 @@
+use card::Card;
+use card::CardSet;
+use deal::Deal;
+use engine;
+use engine::Engine;
+use error::Error;
+use error::Result;
+use game_status::GameStatus;
+use game_status::PlayerName;
+use rules;
+use scenarios;
+use strategy::CardStrategy;
+use strategy::DefensiveCardStrategy;
+use strategy::Registry;
+
+use libc;
+
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+
+const BOLD: &'static str = "\x1b[1m";
+const DIM: &'static str = "\x1b[2m";
+const RESET: &'static str = "\x1b[0m";
+
+const PLAY_HELP: &'static str = "Play a card such as QS or 10H; h shows the deals so far, ? what \
+                                 defensive would play, s NAME saves the situation as a scenario \
+                                 expecting the card you play next, q quits.";
+
+/// A seat at the table for someone playing at the terminal. They are shown the table and asked
+/// for their cards whenever it is their turn.
+#[derive(Debug)]
+pub struct TerminalPlayer {
+    player_name: PlayerName,
+    scenario_dir: PathBuf,
+    color: bool,
+    /// Set once the player quits, after which the rest of the round is played for them.
+    quit: Arc<AtomicBool>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Play(Card),
+    History,
+    Hint,
+    Save(String),
+    Quit,
+}
+
+impl TerminalPlayer {
+    pub fn new(
+        player_name: PlayerName,
+        scenario_dir: PathBuf,
+        color: bool,
+        quit: Arc<AtomicBool>,
+    ) -> TerminalPlayer {
+        TerminalPlayer {
+            player_name: player_name,
+            scenario_dir: scenario_dir,
+            color: color,
+            quit: quit,
+        }
+    }
+
+    fn has_quit(&self) -> bool {
+        self.quit.load(Ordering::SeqCst)
+    }
+
+    /// The next line typed, or `None` once the input ends, which counts as quitting.
+    fn read_line(&self, prompt: &str) -> Option<String> {
+        print!("{} ", prompt);
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match io::stdin().read_line(&mut line) {
+            Ok(0) | Err(_) => {
+                self.quit.store(true, Ordering::SeqCst);
+                None
+            }
+            Ok(_) => Some(line),
+        }
+    }
+}
+
+impl CardStrategy for TerminalPlayer {
+    fn player_name(&self) -> &PlayerName {
+        &self.player_name
+    }
+
+    fn pass_cards(&mut self, game_status: &GameStatus) -> Vec<Card> {
+        let count = game_status.round_parameters.cards_to_pass();
+        let fallback = || {
+            rules::fallback_pass(game_status.my_initial_hand, &game_status.round_parameters)
+        };
+        if count == 0 || self.has_quit() {
+            return fallback();
+        }
+        print!("{}", table(game_status, &self.player_name, self.color));
+        let receiver = game_status
+            .pass_receiver(&self.player_name)
+            .map_or(String::new(), |receiver| format!(", to {}", receiver));
+        let prompt = format!(
+            "Pass {} cards {}{}, or q to quit:",
+            count,
+            game_status.pass_direction(),
+            receiver
+        );
+        loop {
+            let line = match self.read_line(&prompt) {
+                Some(line) => line,
+                None => return fallback(),
+            };
+            if line.trim() == "q" {
+                self.quit.store(true, Ordering::SeqCst);
+                return fallback();
+            }
+            match pass(&line, game_status.my_initial_hand, count) {
+                Ok(cards) => return cards,
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    fn play_card(&mut self, game_status: &GameStatus) -> Card {
+        let legal_plays = game_status.legal_plays();
+        let fallback = || {
+            rules::fallback_play(legal_plays, &game_status.round_parameters)
+                .expect("No valid cards to play!")
+        };
+        if self.has_quit() {
+            return fallback();
+        }
+        print!("{}", table(game_status, &self.player_name, self.color));
+        let mut scenario: Option<String> = None;
+        loop {
+            let line = match self.read_line("Your card (or h, ?, s NAME, q):") {
+                Some(line) => line,
+                None => return fallback(),
+            };
+            match command(&line) {
+                Ok(Command::Play(card)) if legal_plays.contains(&card) => {
+                    if let Some(name) = scenario.as_ref() {
+                        let saved = scenarios::save(
+                            &self.scenario_dir,
+                            name,
+                            game_status,
+                            &self.player_name,
+                            card,
+                        );
+                        match saved {
+                            Ok(path) => println!("Saved {} expecting {}", path.display(), card),
+                            Err(e) => println!("Could not save the scenario: {}", e),
+                        }
+                    }
+                    return card;
+                }
+                Ok(Command::Play(card)) => println!("{} cannot be played now", card),
+                Ok(Command::History) => print!("{}", history(game_status)),
+                Ok(Command::Hint) => {
+                    let hint = DefensiveCardStrategy::new(self.player_name.clone())
+                        .explain(game_status);
+                    match hint {
+                        Some(decision) => print!("{}", decision),
+                        None => println!("No hint"),
+                    }
+                }
+                Ok(Command::Save(name)) => {
+                    println!("The card you play next is kept as expected in {}", name);
+                    scenario = Some(name);
+                }
+                Ok(Command::Quit) => {
+                    self.quit.store(true, Ordering::SeqCst);
+                    return fallback();
+                }
+                Err(_) => println!("{}", PLAY_HELP),
+            }
+        }
+    }
+}
+
+fn command(line: &str) -> Result<Command> {
+    let line = line.trim();
+    let mut parts = line.splitn(2, char::is_whitespace);
+    match (parts.next().unwrap_or_default(), parts.next().map(|rest| rest.trim())) {
+        ("h", None) => Ok(Command::History),
+        ("?", None) => Ok(Command::Hint),
+        ("q", None) => Ok(Command::Quit),
+        ("s", Some(name)) if !name.is_empty() && !name.contains(|c| c == '/' || c == '\\') => {
+            Ok(Command::Save(name.to_owned()))
+        }
+        (card, None) => Card::from_str(card).map(Command::Play),
+        _ => Err(Error::parsing("command", line)),
+    }
+}
+
+/// The cards to pass, from a line such as "QS AH 2C".
+fn pass(line: &str, hand: CardSet, count: usize) -> Result<Vec<Card>> {
+    let cards = line.split_whitespace()
+        .map(Card::from_str)
+        .collect::<Result<Vec<_>>>()?;
+    let distinct = cards.iter().cloned().collect::<CardSet>();
+    if cards.len() != count || distinct.len() != count {
+        return Err(Error::Game(format!("Pass {} different cards", count)));
+    }
+    match cards.iter().find(|card| !hand.contains(card)) {
+        Some(card) => Err(Error::Game(format!("{} is not in your hand", card))),
+        None => Ok(cards),
+    }
+}
+
+/// The table as the player sees it: the points taken this round, the last deal and the one
+/// being played, and the hand with the cards that can be played picked out.
+fn table(game_status: &GameStatus, me: &PlayerName, color: bool) -> String {
+    let mut lines = vec![String::new()];
+    let deal_number = game_status
+        .in_progress_deal
+        .as_ref()
+        .map_or(0, |deal| deal.deal_number);
+    if deal_number == 0 {
+        lines.push(format!("Round {}, passing", game_status.current_round_id));
+    } else {
+        lines.push(format!("Round {}, deal {}", game_status.current_round_id, deal_number));
+    }
+    let points = game_status
+        .game_players
+        .iter()
+        .map(|player| format!("{} {}", player.team_name, player.current_score))
+        .collect::<Vec<_>>();
+    lines.push(format!("Points: {}", points.join(", ")));
+    if game_status.game_deals.is_empty() && !game_status.cards_passed_to_me.is_empty() {
+        let giver = game_status
+            .pass_giver(me)
+            .map_or(String::new(), |giver| format!(" from {}", giver));
+        lines.push(format!(
+            "Passed {}, received {}{}",
+            game_status.cards_passed_by_me,
+            game_status.cards_passed_to_me,
+            giver
+        ));
+    }
+    if let Some(deal) = game_status.game_deals.last() {
+        lines.push(format!("Last deal: {}", describe(deal, game_status)));
+    }
+    if let Some(deal) = game_status.in_progress_deal.as_ref() {
+        if !deal.deal_cards.is_empty() {
+            lines.push(format!("This deal: {}", plays(deal)));
+        }
+    }
+
+    let legal_plays = if game_status.is_my_turn {
+        game_status.legal_plays()
+    } else {
+        CardSet::new()
+    };
+    let hand = game_status
+        .my_current_hand
+        .iter()
+        .map(|card| match (color, legal_plays.contains(&card)) {
+            (true, true) => format!("{}{}{}", BOLD, card, RESET),
+            (true, false) if game_status.is_my_turn => format!("{}{}{}", DIM, card, RESET),
+            (false, true) => format!("[{}]", card),
+            _ => format!("{}", card),
+        })
+        .collect::<Vec<_>>();
+    lines.push(format!("Hand: {}", hand.join(" ")));
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// The deals finished this round, in order.
+fn history(game_status: &GameStatus) -> String {
+    game_status
+        .game_deals
+        .iter()
+        .map(|deal| format!("{: >2}. {}\n", deal.deal_number, describe(deal, game_status)))
+        .collect()
+}
+
+fn plays(deal: &Deal) -> String {
+    deal.deal_cards
+        .iter()
+        .map(|deal_card| format!("{} {}", deal_card.player_name, deal_card.card))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A finished deal, who took it and for how many points.
+fn describe(deal: &Deal, game_status: &GameStatus) -> String {
+    let points = deal.deal_cards
+        .iter()
+        .map(|deal_card| game_status.round_parameters.points(&deal_card.card))
+        .sum::<i32>();
+    match deal.deal_winner {
+        Some(ref winner) => format!("{}; {} took {} points", plays(deal), winner, points),
+        None => plays(deal),
+    }
+}
+
+/// Plays a game at the terminal against bots, which are seated to the player's left in the
+/// order given and repeated to fill the table. The scores are shown after every round.
+pub fn play(
+    player_name: PlayerName,
+    bot_names: &[&str],
+    parameters: &Value,
+    seed: usize,
+    scenario_dir: PathBuf,
+    color: bool,
+) -> Result<()> {
+    if bot_names.is_empty() || bot_names.len() >= engine::PLAYER_COUNT {
+        return Err(Error::Game(
+            format!("Between 1 and {} bots are needed", engine::PLAYER_COUNT - 1),
+        ));
+    }
+    let quit = Arc::new(AtomicBool::new(false));
+    let registry = Registry::standard();
+    let mut seats = vec![player_name.clone()];
+    let mut strategies: Vec<Box<CardStrategy>> = vec![
+        Box::new(TerminalPlayer::new(player_name, scenario_dir, color, quit.clone())),
+    ];
+    for (index, bot_name) in bot_names.iter().cycle().take(engine::PLAYER_COUNT - 1).enumerate() {
+        let bot = PlayerName::new(format!("{}:{}", index + 2, bot_name));
+        if seats.contains(&bot) {
+            return Err(Error::Game(format!("{} is already playing", bot)));
+        }
+        strategies.push(registry.create(bot_name, bot.clone(), parameters)?);
+        seats.push(bot);
+    }
+
+    let mut engine = Engine::new(strategies, seed);
+    let mut totals = BTreeMap::new();
+    for round_id in 1..engine::ROUND_COUNT + 1 {
+        let result = engine.play_round(round_id);
+        let scores = result.scores();
+        for (player_name, score) in &scores {
+            *totals.entry(player_name.clone()).or_insert(0) += *score;
+        }
+        println!();
+        println!("Round {} scores: {}", round_id, standings(&seats, &scores));
+        if let Some(ref shooter) = result.shooter {
+            println!("{} shot the moon", shooter);
+        }
+        println!("Game scores: {}", standings(&seats, &totals));
+        if quit.load(Ordering::SeqCst) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Whether the output is shown on a terminal, where the cards that can be played are picked
+/// out in bold rather than in brackets.
+#[cfg(unix)]
+pub fn is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
+
+#[cfg(not(unix))]
+pub fn is_terminal() -> bool {
+    false
+}
+
+fn standings(seats: &[PlayerName], scores: &BTreeMap<PlayerName, i32>) -> String {
+    seats
+        .iter()
+        .map(|player_name| {
+            format!("{} {}", player_name, scores.get(player_name).cloned().unwrap_or_default())
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::Rank::*;
+    use card::Suit::*;
+
+    fn game_status() -> GameStatus {
+        GameStatus::from_str(
+            "players: North, East, South, West
+             me: South
+             round: 3
+             passed: QS 10H 9C
+             received: 3D 4D 5D
+             hand: 3D 4D 5D 6H 7H 8H 9H 2S 3S 4S 5S 6S
+             deal: North 2C, East 5C, South 4C, West 3C
+             deal: East AD",
+        ).unwrap()
+    }
+
+    #[test]
+    fn reads_commands() {
+        assert_eq!(Command::Play(Queen.of(Spade)), command(" QS\n").unwrap());
+        assert_eq!(Command::Play(Ten.of(Heart)), command("\u{2665}10").unwrap());
+        assert_eq!(Command::History, command("h").unwrap());
+        assert_eq!(Command::Hint, command("?").unwrap());
+        assert_eq!(Command::Save("duck 2".to_owned()), command("s duck 2").unwrap());
+        assert_eq!(Command::Quit, command("q").unwrap());
+        assert!(command("s").is_err());
+        assert!(command("s ../duck").is_err());
+        assert!(command("s ..\\duck").is_err());
+        assert!(command("QS AH").is_err());
+    }
+
+    #[test]
+    fn reads_passes() {
+        let hand = game_status().my_initial_hand;
+        let cards = pass("QS 10h \u{2663}9", hand, 3).unwrap();
+
+        assert_eq!(vec![Queen.of(Spade), Ten.of(Heart), Nine.of(Club)], cards);
+        assert!(pass("QS 10H", hand, 3).is_err());
+        assert!(pass("QS QS 10H", hand, 3).is_err());
+        assert!(pass("QS 10H AC", hand, 3).is_err());
+    }
+
+    #[test]
+    fn shows_the_table() {
+        let game_status = game_status();
+        let me = PlayerName::new("South");
+        let plain = table(&game_status, &me, false);
+
+        assert!(plain.contains("Round 3, deal 2"));
+        assert!(plain.contains("Points: North 0, East 0, South 0, West 0"));
+        assert!(plain.contains("Last deal: North ♣2, East ♣5, South ♣4, West ♣3; East took 0"));
+        assert!(plain.contains("This deal: East ♦A"));
+        assert!(plain.contains("Hand: ♠2 ♠3 ♠4 ♠5 ♠6 ♥6 ♥7 ♥8 ♥9 [♦3] [♦4] [♦5]"));
+        assert!(table(&game_status, &me, true).contains(&format!("{}♦3{}", BOLD, RESET)));
+        assert_eq!(
+            " 1. North ♣2, East ♣5, South ♣4, West ♣3; East took 0 points\n",
+            history(&game_status)
+        );
+    }
+}
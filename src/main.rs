@@ -13,27 +13,76 @@ extern crate serde_json;
 #[macro_use]
 extern crate serde_derive;
 extern crate clap;
+extern crate rand;
+extern crate toml;
+extern crate libc;
+extern crate flate2;
 
 #[macro_use]
 mod macros;
 
+mod accounts;
+mod archive;
 mod card;
+mod credentials;
 mod hearts_client;
 mod game_status;
 mod deal;
 mod strategy;
 mod error;
+mod rules;
+mod engine;
+mod inference;
+mod interactive;
+mod opponents;
+mod optimizer;
+mod server;
+mod replay;
+mod retry;
+mod scenarios;
+mod solver;
+mod stats;
+mod tournament;
 
+use accounts::Accounts;
+use archive::Archive;
+use error::Error;
 use hearts_client::HeartsClient;
-use hearts_client::Password;
 use game_status::PlayerName;
-use strategy::DefensiveCardStrategy;
+use opponents::Opponents;
+use optimizer::Optimizer;
+use strategy::DefensiveParams;
+use strategy::Registry;
+use replay::Replay;
+use retry::RetryPolicy;
+use scenarios::Scenarios;
+use server::HeartsServer;
+use stats::Stats;
+use tournament::Tournament;
 
 use clap::App;
+use clap::AppSettings;
+use clap::ArgMatches;
+use clap::SubCommand;
+
+use env_logger::LogBuilder;
+use log::LogRecord;
+use serde_json::Value;
+
+use std::env;
+use std::fs::File;
+use std::io;
+use std::io::BufRead;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::thread;
+use std::time::Duration;
 
 #[allow(dead_code)]
 fn main() {
-    env_logger::init().unwrap();
+    init_logging();
 
     let cli_options = App::new("hearts")
         .version("0.0.1")
@@ -41,21 +90,452 @@ fn main() {
         .about("Plays hearts for RBS Code Comp Nov 2015")
         .args_from_usage(
             "-u --user=<USER> 'Sets the player name'
-             -p --password=<PASSWORD> 'Sets the player password'
+             -p --password=[PASSWORD] 'Sets the player password, visible to other users'
+             --password-file=[FILE] 'Reads the password from a file only you can read'
              -s --server=<SERVER> 'Sets the hearts server'
-             -r --repeat 'After game ends, attempt to join again'",
+             -r --repeat 'After game ends, attempt to join again'
+             --archive 'Logs games to compressed archives in game_log, with an index'
+             --strategy=[STRATEGY] 'Sets the strategy to play with (default defensive)'
+             --strategy-config=[FILE] 'JSON or TOML file of strategy parameters'
+             --retries=[ATTEMPTS] 'Attempts at each request to the server (default 4)'
+             --retry-delay=[MILLIS] 'Wait before the first retry, doubling after (default 200)'
+             --max-retry-delay=[MILLIS] 'Longest wait between retries (default 2000)'",
+        )
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(
+            SubCommand::with_name("server")
+                .about("Runs a local stand-in for the hearts server")
+                .args_from_usage(
+                    "-b --bind=[ADDRESS] 'Sets the address to listen on (default 127.0.0.1:8080)'
+                     -w --wait=[SECONDS] 'Seconds to wait for players before filling seats with dummies (default 10)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tournament")
+                .about("Plays strategies against each other over many seeded games")
+                .args_from_usage(
+                    "-g --games=[GAMES] 'Number of games to play (default 1000)'
+                     --seed=[SEED] 'Seed for the first deal (default 0)'
+                     <STRATEGY>... 'Strategies to play, repeated to fill the table'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Shows a logged game deal by deal")
+                .args_from_usage(
+                    "-d --log-dir=[DIR] 'Directory the game was logged to (default game_log)'
+                     --step 'Waits for enter after each deal, q quits'
+                     <GAME_ID> 'Game to replay'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("archive")
+                .about("Archives the games logged as directories of snapshots, or lists them")
+                .args_from_usage(
+                    "-d --log-dir=[DIR] 'Directory the games were logged to (default game_log)'
+                     -o --output=[DIR] 'Directory to archive them to (default the log directory)'
+                     -l --list 'Lists the games archived in the log directory instead'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("stats")
+                .about("Sums up how we did in the logged games, by round and by opponent")
+                .args_from_usage(
+                    "-d --log-dir=[DIR] 'Directory the games were logged to (default game_log)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("opponents")
+                .about("Shows what the client has learnt of its opponents")
+                .args_from_usage(
+                    "-d --log-dir=[DIR] 'Directory the games were logged to (default game_log)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("scenarios")
+                .about("Checks the cards a strategy plays in saved game states")
+                .args_from_usage(
+                    "-d --dir=[DIR] 'Directory of scenarios (default samples/scenarios)'
+                     -m --manifest=[FILE] 'Expected cards by scenario (default DIR/expected.json)'
+                     --strategy-config=[FILE] 'JSON or TOML file of strategy parameters'
+                     [STRATEGY] 'Strategy to check (default defensive)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("explain")
+                .about("Ranks the cards a strategy would play in a saved game state, and why")
+                .args_from_usage(
+                    "--strategy-config=[FILE] 'JSON or TOML file of strategy parameters'
+                     <FILE> 'Game state as the server sends it, or a .txt scenario'
+                     [STRATEGY] 'Strategy to explain (default defensive)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("interactive")
+                .about("Plays a game at the terminal against strategies")
+                .args_from_usage(
+                    "-n --name=[NAME] 'Your name at the table (default You)'
+                     --seed=[SEED] 'Seed for the deal (default random)'
+                     --strategy-config=[FILE] 'JSON or TOML file of strategy parameters'
+                     --scenario-dir=[DIR] 'Where scenarios are saved (default scenarios/saved)'
+                     --plain 'Marks the cards that can be played with brackets, not bold'
+                     [BOT]... 'Strategies to play against (default defensive)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("optimize")
+                .about("Tunes the defensive strategy's weights by playing other strategies")
+                .args_from_usage(
+                    "-g --generations=[GENERATIONS] 'Number of generations (default 20)'
+                     -n --population=[VARIANTS] 'Variants tried each generation (default 8)'
+                     --games=[GAMES] 'Games each variant plays (default 40)'
+                     --seed=[SEED] 'Seed for the first deal (default 0)'
+                     --strategy-config=[FILE] 'Reads the weights to start from'
+                     -o --output=[FILE] 'Writes the weights found as JSON'
+                     [OPPONENT]... 'Strategies to play against (default defensive)'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("accounts")
+                .about("Plays for several accounts at once, each on its own thread")
+                .args_from_usage(
+                    "-d --log-dir=[DIR] 'Logs games under DIR/<user> (default game_log)'
+                     --archive 'Logs games to compressed archives, with an index'
+                     --retries=[ATTEMPTS] 'Attempts at each request to the server (default 4)'
+                     --retry-delay=[MILLIS] 'Wait before the first retry (default 200)'
+                     --max-retry-delay=[MILLIS] 'Longest wait between retries (default 2000)'
+                     <FILE> 'JSON or TOML file listing the accounts'",
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("strategies").about("Lists the strategies that can be played"),
         )
         .get_matches();
 
-    let player_name = PlayerName::new(cli_options.value_of("USER").unwrap());
-    let password = Password::new(cli_options.value_of("PASSWORD").unwrap());
-    let server = cli_options.value_of("SERVER").unwrap();
+    match cli_options.subcommand() {
+        ("server", Some(server_options)) => serve(server_options),
+        ("tournament", Some(tournament_options)) => tournament(tournament_options),
+        ("replay", Some(replay_options)) => replay(replay_options),
+        ("archive", Some(archive_options)) => archive(archive_options),
+        ("stats", Some(stats_options)) => stats(stats_options),
+        ("opponents", Some(opponents_options)) => opponents(opponents_options),
+        ("scenarios", Some(scenarios_options)) => scenarios(scenarios_options),
+        ("explain", Some(explain_options)) => explain(explain_options),
+        ("interactive", Some(interactive_options)) => interactive(interactive_options),
+        ("optimize", Some(optimize_options)) => optimize(optimize_options),
+        ("accounts", Some(accounts_options)) => accounts(accounts_options),
+        ("strategies", Some(_)) => strategies(),
+        _ => play(&cli_options),
+    }
+}
+
+fn play(cli_options: &ArgMatches) {
+    let player_name = PlayerName::new(cli_options.value_of("user").unwrap());
+    let password = match credentials::password(
+        &player_name,
+        cli_options.value_of("password"),
+        cli_options.value_of("password-file"),
+    ) {
+        Ok(password) => password,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
+    let server = cli_options.value_of("server").unwrap();
     let repeat = cli_options.is_present("repeat");
+    let retry_policy = retry_policy(cli_options);
 
     info!("Start Game");
 
-    let strategy = DefensiveCardStrategy::new(player_name);
+    let strategy_name = cli_options.value_of("strategy").unwrap_or("defensive");
+    let strategy = match strategy_parameters(cli_options).and_then(|parameters| {
+        Registry::standard().create(strategy_name, player_name, &parameters)
+    }) {
+        Ok(strategy) => strategy,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
 
-    let client = HeartsClient::new(password, server, strategy, repeat);
+    accounts::stop_on_interrupt();
+    let client = HeartsClient::new(password, server, strategy, retry_policy, repeat)
+        .archive(cli_options.is_present("archive"))
+        .stop_on(&accounts::INTERRUPTED);
     client.play();
 }
+
+fn accounts(cli_options: &ArgMatches) {
+    let log_dir = cli_options.value_of("log-dir").unwrap_or("game_log");
+    match Accounts::load(cli_options.value_of("FILE").unwrap()) {
+        Ok(accounts) => {
+            let archive = cli_options.is_present("archive");
+            print!("{}", accounts.play(log_dir, retry_policy(cli_options), archive));
+        }
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn retry_policy(cli_options: &ArgMatches) -> RetryPolicy {
+    let mut retry_policy = RetryPolicy::default();
+    if let Some(attempts) = cli_options.value_of("retries") {
+        retry_policy.attempts = attempts.parse().expect("Invalid number of attempts");
+    }
+    let millis = |name| {
+        cli_options.value_of(name).map(|millis| {
+            Duration::from_millis(millis.parse().expect("Invalid number of milliseconds"))
+        })
+    };
+    retry_policy.initial_delay = millis("retry-delay").unwrap_or(retry_policy.initial_delay);
+    retry_policy.max_delay = millis("max-retry-delay").unwrap_or(retry_policy.max_delay);
+    retry_policy
+}
+
+/// Logs as `env_logger` does by default, with the name of the thread first when it has one,
+/// as each account's does.
+fn init_logging() {
+    let mut builder = LogBuilder::new();
+    builder.format(|record: &LogRecord| {
+        let prefix = match thread::current().name() {
+            Some(name) if name != "main" => format!("[{}] ", name),
+            _ => String::new(),
+        };
+        format!(
+            "{}{}:{}: {}",
+            prefix,
+            record.level(),
+            record.location().module_path(),
+            record.args()
+        )
+    });
+    if let Ok(filters) = env::var("RUST_LOG") {
+        builder.parse(&filters);
+    }
+    builder.init().unwrap();
+}
+
+fn serve(cli_options: &ArgMatches) {
+    let address = cli_options.value_of("bind").unwrap_or("127.0.0.1:8080");
+    let wait = cli_options
+        .value_of("wait")
+        .map(|seconds| seconds.parse().expect("Invalid number of seconds"))
+        .unwrap_or(10);
+
+    HeartsServer::new(Duration::from_secs(wait))
+        .run(address)
+        .unwrap_or_else(|e| error!("Server failed: {}", e));
+}
+
+fn tournament(cli_options: &ArgMatches) {
+    let strategy_names = cli_options.values_of("STRATEGY").unwrap().collect::<Vec<_>>();
+    let games = cli_options
+        .value_of("games")
+        .map(|games| games.parse().expect("Invalid number of games"))
+        .unwrap_or(1000);
+    let seed = cli_options
+        .value_of("seed")
+        .map(|seed| seed.parse().expect("Invalid seed"))
+        .unwrap_or(0);
+
+    match Tournament::new(&strategy_names, games, seed) {
+        Ok(mut tournament) => {
+            tournament.run();
+            print!("{}", tournament);
+        }
+        Err(e) => error!("{}", e),
+    }
+}
+
+fn replay(cli_options: &ArgMatches) {
+    let game_id = cli_options.value_of("GAME_ID").unwrap();
+    let log_dir = cli_options.value_of("log-dir").unwrap_or("game_log");
+    let step = cli_options.is_present("step");
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let next = || if step {
+        match lines.next() {
+            Some(Ok(line)) => line.trim() != "q",
+            _ => false,
+        }
+    } else {
+        true
+    };
+
+    let stdout = io::stdout();
+    Replay::load(log_dir, game_id)
+        .and_then(|replay| replay.show(&mut stdout.lock(), next))
+        .unwrap_or_else(|e| error!("Replay failed: {}", e));
+}
+
+fn archive(cli_options: &ArgMatches) {
+    let log_dir = cli_options.value_of("log-dir").unwrap_or("game_log");
+    let archive = Archive::new(cli_options.value_of("output").unwrap_or(log_dir));
+
+    let result = if cli_options.is_present("list") {
+        archive.index()
+    } else {
+        archive.convert(log_dir)
+    };
+    match result {
+        Ok(entries) => {
+            for entry in entries {
+                let scores = entry
+                    .participants
+                    .iter()
+                    .map(|player| format!("{} {}", player.player_name, player.score))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let cancelled = if entry.cancelled { " (cancelled)" } else { "" };
+                println!("{} {} rounds{}: {}", entry.game_id, entry.rounds, cancelled, scores);
+            }
+        }
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn stats(cli_options: &ArgMatches) {
+    let log_dir = cli_options.value_of("log-dir").unwrap_or("game_log");
+    match Stats::collect(log_dir) {
+        Ok(stats) => print!("{}", stats),
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn opponents(cli_options: &ArgMatches) {
+    let log_dir = cli_options.value_of("log-dir").unwrap_or("game_log");
+    match Opponents::load(Path::new(log_dir).join(opponents::FILE)) {
+        Ok(opponents) => print!("{}", opponents),
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn scenarios(cli_options: &ArgMatches) {
+    let dir = cli_options.value_of("dir").unwrap_or("samples/scenarios");
+    let manifest = cli_options.value_of("manifest").map(PathBuf::from);
+    let strategy_name = cli_options.value_of("STRATEGY").unwrap_or("defensive");
+
+    match strategy_parameters(cli_options)
+        .and_then(|parameters| Scenarios::run(dir, manifest, strategy_name, &parameters))
+    {
+        Ok(scenarios) => {
+            print!("{}", scenarios);
+            if scenarios.failures() > 0 {
+                process::exit(1);
+            }
+        }
+        Err(e) => error!("{}", e),
+    }
+}
+
+fn explain(cli_options: &ArgMatches) {
+    let path = Path::new(cli_options.value_of("FILE").unwrap());
+    let strategy_name = cli_options.value_of("STRATEGY").unwrap_or("defensive");
+
+    let result = scenarios::read_game_status(path).and_then(|game_status| {
+        let player_name = match game_status.game_players.iter().find(|player| player.has_turn) {
+            Some(player) if game_status.is_my_turn => player.team_name.clone(),
+            _ => return Err(Error::Game(format!("It is not our turn in {}", path.display()))),
+        };
+        let parameters = strategy_parameters(cli_options)?;
+        let strategy = Registry::standard().create(strategy_name, player_name, &parameters)?;
+        strategy.explain(&game_status).ok_or_else(|| {
+            Error::Game(format!("{} cannot explain its plays", strategy_name))
+        })
+    });
+    match result {
+        Ok(decision) => print!("{}", decision),
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn interactive(cli_options: &ArgMatches) {
+    let player_name = PlayerName::new(cli_options.value_of("name").unwrap_or("You"));
+    let bot_names = cli_options
+        .values_of("BOT")
+        .map_or(vec!["defensive"], |values| values.collect());
+    let seed = cli_options
+        .value_of("seed")
+        .map_or_else(rand::random, |seed| seed.parse().expect("Invalid seed"));
+    let scenario_dir = cli_options.value_of("scenario-dir").unwrap_or("scenarios/saved");
+    let color = !cli_options.is_present("plain") && interactive::is_terminal();
+
+    let result = strategy_parameters(cli_options).and_then(|parameters| {
+        interactive::play(
+            player_name,
+            &bot_names,
+            &parameters,
+            seed,
+            PathBuf::from(scenario_dir),
+            color,
+        )
+    });
+    if let Err(e) = result {
+        error!("{}", e);
+        process::exit(1);
+    }
+}
+
+fn optimize(cli_options: &ArgMatches) {
+    let opponent_names = cli_options
+        .values_of("OPPONENT")
+        .map_or(vec!["defensive"], |values| values.collect());
+    let number = |name, default| {
+        cli_options
+            .value_of(name)
+            .map(|value| value.parse().expect("Invalid number"))
+            .unwrap_or(default)
+    };
+    let generations = number("generations", 20);
+    let population = number("population", 8);
+    let games = number("games", 40);
+    let seed = number("seed", 0);
+
+    let result = strategy_parameters(cli_options)
+        .and_then(|parameters| strategy::parameters::<DefensiveParams>(&parameters))
+        .and_then(|params| Optimizer::new(params, &opponent_names, population, games, seed))
+        .and_then(|mut optimizer| optimizer.run(generations).map(|_| optimizer))
+        .and_then(|optimizer| {
+            print!("{}", optimizer);
+            let params = serde_json::to_string_pretty(optimizer.params())?;
+            match cli_options.value_of("output") {
+                Some(path) => writeln!(File::create(path)?, "{}", params)?,
+                None => println!("{}", params),
+            }
+            Ok(())
+        });
+    if let Err(e) = result {
+        error!("Optimizing failed: {}", e);
+        process::exit(1);
+    }
+}
+
+fn strategies() {
+    let registry = Registry::standard();
+    for name in registry.names() {
+        println!("{: <12} {}", name, registry.description(name).unwrap_or_default());
+    }
+}
+
+fn strategy_parameters(cli_options: &ArgMatches) -> error::Result<Value> {
+    match cli_options.value_of("strategy-config") {
+        Some(path) => strategy::read_parameters(path),
+        None => Ok(Value::Null),
+    }
+}
@@ -0,0 +1,391 @@
+use card::Card;
+use card::CardSet;
+use card::Rank;
+use card::Suit;
+use deal::Deal;
+use error::Result;
+use game_status::GameParticipant;
+use game_status::PlayerName;
+
+use serde_json;
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+
+/// The file the client keeps its model of the opponents in, in its log directory.
+pub const FILE: &'static str = "opponents.json";
+
+/// Deals up to this one are early for the queen of spades to be played in.
+const EARLY_DEALS: u32 = 4;
+/// A player who takes this many of the hearts and the queen of spades in a round was taking
+/// them on purpose, whether they got the rest or not.
+const MOON_ATTEMPT_CARDS: usize = 10;
+/// Rates are only given once a player has been seen this often, in the follows, queen plays,
+/// passed cards or rounds each rate is out of.
+const MIN_FOLLOWS: u32 = 20;
+const MIN_QUEEN_PLAYS: u32 = 3;
+const MIN_CARDS_PASSED: u32 = 9;
+const MIN_ROUNDS: u32 = 5;
+
+/// What has been seen of each opponent over the games played against them, learnt deal by
+/// deal and kept between runs.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct Opponents {
+    players: BTreeMap<PlayerName, Profile>,
+}
+
+/// How often an opponent did what they did, counted over every round seen.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct Profile {
+    pub rounds: u32,
+    /// Cards played in the suit led, by someone other than the leader.
+    pub follows: u32,
+    /// Follows under a card of the suit already played in the deal.
+    pub ducks: u32,
+    pub queen_plays: u32,
+    pub early_queen_plays: u32,
+    /// Plays of the queen of spades on a deal led in another suit.
+    pub queen_dumps: u32,
+    /// Passes seen, which are those made to us.
+    pub passes: u32,
+    pub cards_passed: u32,
+    pub hearts_passed: u32,
+    /// The queen, king and ace of spades passed.
+    pub high_spades_passed: u32,
+    pub moon_attempts: u32,
+    pub moons_shot: u32,
+}
+
+impl Profile {
+    pub fn duck_rate(&self) -> Option<f32> {
+        rate(self.ducks, self.follows, MIN_FOLLOWS)
+    }
+
+    /// How often they play the queen of spades early, of the times they play it.
+    pub fn early_queen_rate(&self) -> Option<f32> {
+        rate(self.early_queen_plays, self.queen_plays, MIN_QUEEN_PLAYS)
+    }
+
+    pub fn heart_pass_rate(&self) -> Option<f32> {
+        rate(self.hearts_passed, self.cards_passed, MIN_CARDS_PASSED)
+    }
+
+    /// How often they try to shoot the moon, per round.
+    pub fn moon_rate(&self) -> Option<f32> {
+        rate(self.moon_attempts, self.rounds, MIN_ROUNDS)
+    }
+}
+
+fn rate(count: u32, out_of: u32, at_least: u32) -> Option<f32> {
+    if out_of >= at_least && out_of > 0 {
+        Some(count as f32 / out_of as f32)
+    } else {
+        None
+    }
+}
+
+impl Opponents {
+    /// Reads the model from the file, starting afresh if there is none.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Opponents> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(Opponents::default());
+        }
+        let mut string = String::new();
+        File::open(path)?.read_to_string(&mut string)?;
+        Ok(serde_json::from_str(&string)?)
+    }
+
+    /// Writes the profiles to a file beside `path` and renames it over `path`, so that a
+    /// client stopped while saving leaves the profiles it last saved.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            fs::DirBuilder::new().recursive(true).create(dir)?;
+        }
+        let string = serde_json::to_string_pretty(self)?;
+        let mut file_name = path.file_name().map(OsString::from).unwrap_or_default();
+        file_name.push(format!(".{}.tmp", process::id()));
+        let temp_path = path.with_file_name(file_name);
+        let mut file = File::create(&temp_path)?;
+        file.write_all(string.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn profile(&self, player_name: &PlayerName) -> Option<&Profile> {
+        self.players.get(player_name)
+    }
+
+    /// The profiles of the players at the table other than us, as given to strategies.
+    pub fn at_table(&self, game_players: &[GameParticipant], me: &PlayerName) -> Opponents {
+        Opponents {
+            players: game_players
+                .iter()
+                .map(|player| &player.team_name)
+                .filter(|player_name| *player_name != me)
+                .filter_map(|player_name| {
+                    self.players.get(player_name).map(|profile| {
+                        (player_name.clone(), profile.clone())
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    /// Learns how everyone but us followed, and where they played the queen of spades, in a
+    /// finished deal.
+    pub fn learn_deal(&mut self, deal: &Deal, me: &PlayerName) {
+        let queen = Rank::Queen.of(Suit::Spade);
+        let mut highest: Option<Rank> = None;
+        for (index, deal_card) in deal.deal_cards.iter().enumerate() {
+            let card = deal_card.card;
+            let following = index > 0 && deal.suit == Some(card.suit);
+            if deal_card.player_name != *me {
+                let profile = self.players.entry(deal_card.player_name.clone()).or_insert_with(
+                    Profile::default,
+                );
+                if following {
+                    profile.follows += 1;
+                    if highest.map_or(false, |highest| card.rank < highest) {
+                        profile.ducks += 1;
+                    }
+                }
+                if card == queen {
+                    profile.queen_plays += 1;
+                    if deal.deal_number <= EARLY_DEALS {
+                        profile.early_queen_plays += 1;
+                    }
+                    if deal.suit != Some(Suit::Spade) {
+                        profile.queen_dumps += 1;
+                    }
+                }
+            }
+            let highest_so_far = highest.map_or(true, |highest| card.rank > highest);
+            if deal.suit == Some(card.suit) && highest_so_far {
+                highest = Some(card.rank);
+            }
+        }
+    }
+
+    /// Learns what a player passed to us.
+    pub fn learn_pass(&mut self, giver: &PlayerName, cards: CardSet) {
+        let profile = self.players.entry(giver.clone()).or_insert_with(Profile::default);
+        profile.passes += 1;
+        profile.cards_passed += cards.len() as u32;
+        profile.hearts_passed += cards.suit(Suit::Heart).len() as u32;
+        profile.high_spades_passed += cards
+            .suit(Suit::Spade)
+            .iter()
+            .filter(|card| card.rank > Rank::Jack)
+            .count() as u32;
+    }
+
+    /// Learns who tried to shoot the moon in a finished round, and counts the round for
+    /// everyone but us.
+    pub fn learn_round(
+        &mut self,
+        game_players: &[GameParticipant],
+        deals: &[Deal],
+        me: &PlayerName,
+    ) {
+        let moon_cards = Card::all()
+            .iter()
+            .filter(|card| shooting_card(card))
+            .count();
+        for player in game_players.iter().filter(|player| player.team_name != *me) {
+            let taken = deals
+                .iter()
+                .filter(|deal| deal.deal_winner.as_ref() == Some(&player.team_name))
+                .flat_map(|deal| deal.deal_cards.iter())
+                .filter(|deal_card| shooting_card(&deal_card.card))
+                .count();
+            let profile = self.players.entry(player.team_name.clone()).or_insert_with(
+                Profile::default,
+            );
+            profile.rounds += 1;
+            if taken >= MOON_ATTEMPT_CARDS {
+                profile.moon_attempts += 1;
+            }
+            if taken == moon_cards {
+                profile.moons_shot += 1;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Opponents {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.players
+            .keys()
+            .map(|player_name| format!("{}", player_name).chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(8);
+        writeln!(
+            f,
+            "{: <w$} {: >6} {: >6} {: >8} {: >8} {: >13} {: >11} {: >4}",
+            "Opponent",
+            "Rounds",
+            "Ducks",
+            "Early Q♠",
+            "Q♠ dumps",
+            "Hearts passed",
+            "Moons tried",
+            "Shot",
+            w = width
+        )?;
+        for (player_name, profile) in &self.players {
+            writeln!(
+                f,
+                "{: <w$} {: >6} {: >6} {: >8} {: >8} {: >13} {: >11} {: >4}",
+                format!("{}", player_name),
+                profile.rounds,
+                percentage(profile.duck_rate()),
+                percentage(profile.early_queen_rate()),
+                profile.queen_dumps,
+                percentage(profile.heart_pass_rate()),
+                percentage(profile.moon_rate()),
+                profile.moons_shot,
+                w = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A rate as a percentage, or "-" until the player has been seen often enough.
+fn percentage(rate: Option<f32>) -> String {
+    rate.map_or("-".to_owned(), |rate| format!("{:.0}%", rate * 100.0))
+}
+
+fn shooting_card(card: &Card) -> bool {
+    card.suit == Suit::Heart || *card == Rank::Queen.of(Suit::Spade)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use deal::DealCard;
+    use card::Rank::*;
+    use card::Suit::*;
+
+    use std::env;
+
+    fn player(name: &str) -> GameParticipant {
+        GameParticipant {
+            team_name: PlayerName::new(name),
+            left_participant: PlayerName::new(name),
+            number_of_cards_in_hand: 0,
+            has_turn: false,
+            current_score: 0,
+        }
+    }
+
+    fn deal(deal_number: u32, winner: &str, cards: &[(&str, Card)]) -> Deal {
+        Deal {
+            deal_number: deal_number,
+            initiator: Some(PlayerName::new(cards[0].0)),
+            suit: Some(cards[0].1.suit),
+            deal_cards: cards
+                .iter()
+                .map(|&(name, card)| {
+                    DealCard {
+                        player_name: PlayerName::new(name),
+                        card: card,
+                    }
+                })
+                .collect(),
+            deal_winner: Some(PlayerName::new(winner)),
+        }
+    }
+
+    #[test]
+    fn learns_ducks_and_queen_dumps() {
+        let me = PlayerName::new("Me");
+        let mut opponents = Opponents::default();
+        opponents.learn_deal(
+            &deal(
+                2,
+                "Me",
+                &[
+                    ("Me", King.of(Club)),
+                    ("Ann", Two.of(Club)),
+                    ("Bob", Queen.of(Spade)),
+                    ("Cy", Ace.of(Club)),
+                ],
+            ),
+            &me,
+        );
+
+        let ann = opponents.profile(&PlayerName::new("Ann")).unwrap();
+        let bob = opponents.profile(&PlayerName::new("Bob")).unwrap();
+        let cy = opponents.profile(&PlayerName::new("Cy")).unwrap();
+        assert_eq!((1, 1), (ann.follows, ann.ducks));
+        assert_eq!((0, 1), (bob.follows, bob.queen_plays));
+        assert_eq!((1, 1), (bob.early_queen_plays, bob.queen_dumps));
+        assert_eq!((1, 0), (cy.follows, cy.ducks));
+        assert_eq!(None, opponents.profile(&me));
+        assert_eq!(None, ann.duck_rate());
+    }
+
+    #[test]
+    fn learns_passes_and_moon_attempts() {
+        let me = PlayerName::new("Me");
+        let mut opponents = Opponents::default();
+        opponents.learn_pass(
+            &PlayerName::new("Ann"),
+            vec![Queen.of(Spade), Two.of(Heart), Three.of(Club)]
+                .into_iter()
+                .collect(),
+        );
+        let hearts = Card::all().suit(Heart);
+        let deals = hearts
+            .iter()
+            .zip(Card::all().suit(Club).iter())
+            .enumerate()
+            .map(|(index, (heart, club))| {
+                deal(index as u32 + 1, "Ann", &[("Ann", heart), ("Me", club)])
+            })
+            .collect::<Vec<_>>();
+        let players = vec![player("Me"), player("Ann"), player("Bob")];
+        for _ in 0..MIN_ROUNDS {
+            opponents.learn_round(&players, &deals, &me);
+        }
+
+        let ann = opponents.profile(&PlayerName::new("Ann")).unwrap();
+        assert_eq!((1, 3), (ann.passes, ann.cards_passed));
+        assert_eq!((1, 1), (ann.hearts_passed, ann.high_spades_passed));
+        assert_eq!((MIN_ROUNDS, MIN_ROUNDS, 0), (ann.rounds, ann.moon_attempts, ann.moons_shot));
+        assert_eq!(Some(1.0), ann.moon_rate());
+        assert_eq!(Some(0.0), opponents.profile(&PlayerName::new("Bob")).unwrap().moon_rate());
+        let ann_row = vec!["Ann", "5", "-", "-", "0", "-", "100%", "0"];
+        assert!(format!("{}", opponents).lines().any(|line| {
+            line.split_whitespace().collect::<Vec<_>>() == ann_row
+        }));
+
+        let table = opponents.at_table(&players, &PlayerName::new("Ann"));
+        assert_eq!(None, table.profile(&PlayerName::new("Ann")));
+        assert!(table.profile(&PlayerName::new("Bob")).is_some());
+
+        let path = env::temp_dir()
+            .join(format!("hearts-opponents-test-{}", process::id()))
+            .join(FILE);
+        opponents.save(&path).unwrap();
+        opponents.save(&path).unwrap();
+        let loaded = Opponents::load(&path).unwrap();
+        let files = fs::read_dir(path.parent().unwrap()).unwrap().count();
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+        assert_eq!(opponents, loaded);
+        assert_eq!(1, files);
+    }
+}
@@ -0,0 +1,351 @@
+use engine;
+use engine::Engine;
+use error::Error;
+use error::Result;
+use game_status::PlayerName;
+use strategy::CardStrategy;
+use strategy::DefensiveParams;
+use strategy::Registry;
+
+use rand::SeedableRng;
+use rand::StdRng;
+use rand::distributions::IndependentSample;
+use rand::distributions::Normal;
+
+use serde_json;
+use serde_json::Map;
+use serde_json::Number;
+use serde_json::Value;
+
+use std::fmt;
+
+const CANDIDATE: &'static str = "defensive";
+const STEP_GROWTH: f64 = 1.2;
+const STEP_SHRINK: f64 = 0.85;
+/// Points are compared once scaled and rounded, so variants keep at least this scale.
+const MIN_POINTS_SCALE: f64 = 1.0;
+
+/// Tunes the weights of the defensive strategy by self-play, with a simple evolution
+/// strategy: each generation perturbs the current weights at random, plays every variant
+/// against fixed opponents on the same deals, and moves the weights towards the variants
+/// that took the fewest points. The step grows while variants beat the current weights and
+/// shrinks while they do not. The weights that took the fewest points of all those played are
+/// kept as the result.
+pub struct Optimizer {
+    registry: Registry,
+    opponents: Vec<String>,
+    population: usize,
+    games: usize,
+    seed: usize,
+    rng: StdRng,
+    step: f64,
+    params: DefensiveParams,
+    best: Option<(f64, DefensiveParams)>,
+    history: Vec<Generation>,
+}
+
+/// Average points per round taken in one generation, lower being better: by the weights the
+/// generation started from, by the best of their variants and by all of them on average.
+#[derive(Debug, Clone)]
+pub struct Generation {
+    pub current: f64,
+    pub best: f64,
+    pub mean: f64,
+    pub step: f64,
+}
+
+impl Optimizer {
+    /// Each variant plays `games` games, seated in turn at each seat of a table with the
+    /// opponents, which are repeated to fill it.
+    pub fn new(
+        params: DefensiveParams,
+        opponent_names: &[&str],
+        population: usize,
+        games: usize,
+        seed: usize,
+    ) -> Result<Optimizer> {
+        if opponent_names.is_empty() || opponent_names.len() >= engine::PLAYER_COUNT {
+            return Err(Error::Game(format!(
+                "Between 1 and {} opponents are needed",
+                engine::PLAYER_COUNT - 1
+            )));
+        }
+        if population < 2 || games == 0 {
+            return Err(Error::Game(
+                "At least 2 variants and 1 game are needed".to_owned(),
+            ));
+        }
+        let registry = Registry::standard();
+        for opponent_name in opponent_names {
+            registry.create(opponent_name, PlayerName::new(""), &Value::Null)?;
+        }
+        Ok(Optimizer {
+            registry: registry,
+            opponents: opponent_names
+                .iter()
+                .cycle()
+                .take(engine::PLAYER_COUNT - 1)
+                .map(|opponent_name| (*opponent_name).to_owned())
+                .collect(),
+            population: population,
+            games: games,
+            seed: seed,
+            rng: StdRng::from_seed(&[seed]),
+            step: 0.2,
+            params: params,
+            best: None,
+            history: Vec::new(),
+        })
+    }
+
+    pub fn run(&mut self, generations: usize) -> Result<()> {
+        for _ in 0..generations {
+            self.generation()?;
+            let generation = self.history.last().unwrap();
+            info!(
+                "Generation {}: current {:.3}, best {:.3}, step {:.3}",
+                self.history.len(),
+                generation.current,
+                generation.best,
+                generation.step
+            );
+        }
+        Ok(())
+    }
+
+    /// The weights that took the fewest points so far, or the ones to start from before any
+    /// generation.
+    pub fn params(&self) -> &DefensiveParams {
+        self.best.as_ref().map_or(&self.params, |&(_, ref params)| params)
+    }
+
+    /// Average points per round taken by the weights `params` returns, once played.
+    pub fn best_points(&self) -> Option<f64> {
+        self.best.as_ref().map(|&(points, _)| points)
+    }
+
+    fn keep_if_best(&mut self, points: f64, params: &Value) -> Result<()> {
+        if self.best_points().map_or(true, |best| points < best) {
+            self.best = Some((points, serde_json::from_value(params.clone())?));
+        }
+        Ok(())
+    }
+
+    fn generation(&mut self) -> Result<()> {
+        let seed = self.seed + self.history.len() * self.games;
+        let current = serde_json::to_value(&self.params)?;
+        let current_points = self.evaluate(&current, seed)?;
+
+        let mut variants = Vec::new();
+        for _ in 0..self.population {
+            let variant = perturb(&current, self.step, &mut self.rng);
+            let points = self.evaluate(&variant, seed)?;
+            variants.push((points, variant));
+        }
+        variants.sort_by(|&(a, _), &(b, _)| a.partial_cmp(&b).unwrap());
+
+        let mean = variants.iter().map(|&(points, _)| points).sum::<f64>() /
+            variants.len() as f64;
+        let best_points = variants[0].0;
+        self.keep_if_best(current_points, &current)?;
+        self.keep_if_best(best_points, &variants[0].1)?;
+        self.history.push(Generation {
+            current: current_points,
+            best: best_points,
+            mean: mean,
+            step: self.step,
+        });
+        if best_points < current_points {
+            let parents = variants
+                .iter()
+                .take(self.population / 2)
+                .map(|&(_, ref variant)| variant)
+                .collect::<Vec<_>>();
+            self.params = serde_json::from_value(average(&current, &parents))?;
+            self.step *= STEP_GROWTH;
+        } else {
+            self.step *= STEP_SHRINK;
+        }
+        Ok(())
+    }
+
+    /// Average points per round taken by the weights, over the same games for a given seed.
+    fn evaluate(&self, params: &Value, seed: usize) -> Result<f64> {
+        let candidate = PlayerName::new("candidate");
+        let mut total = 0;
+        let mut rounds = 0;
+        for game in 0..self.games {
+            let seat = game % engine::PLAYER_COUNT;
+            let mut strategies = Vec::new();
+            for (index, opponent_name) in self.opponents.iter().enumerate() {
+                let player_name = PlayerName::new(format!("{}:{}", index + 1, opponent_name));
+                strategies.push(self.registry.create(opponent_name, player_name, &Value::Null)?);
+            }
+            let strategy: Box<CardStrategy> =
+                self.registry.create(CANDIDATE, candidate.clone(), params)?;
+            strategies.insert(seat, strategy);
+
+            let result = Engine::new(strategies, seed + game / engine::PLAYER_COUNT).play_game();
+            for round in &result.rounds {
+                total += round.scores()[&candidate];
+                rounds += 1;
+            }
+        }
+        Ok(total as f64 / rounds as f64)
+    }
+}
+
+/// Moves each number by a normally distributed step in proportion to its size, keeping whole
+/// numbers whole.
+fn perturb(params: &Value, step: f64, rng: &mut StdRng) -> Value {
+    let normal = Normal::new(0.0, step);
+    let mut variant =
+        map_numbers(params, |value| value + normal.ind_sample(rng) * value.abs().max(1.0));
+    let points_scale = variant["points_scale"].as_f64();
+    if let Some(points_scale) = points_scale {
+        if let Some(number) = Number::from_f64(points_scale.max(MIN_POINTS_SCALE)) {
+            variant["points_scale"] = Value::Number(number);
+        }
+    }
+    variant
+}
+
+/// Each number in the current weights moved to the average of the parents.
+fn average(current: &Value, parents: &[&Value]) -> Value {
+    let mut index = 0;
+    map_numbers(current, |_| {
+        let sum = parents
+            .iter()
+            .map(|parent| numbers(parent)[index])
+            .sum::<f64>();
+        index += 1;
+        sum / parents.len() as f64
+    })
+}
+
+/// The numbers of the weights, in order.
+fn numbers(params: &Value) -> Vec<f64> {
+    match *params {
+        Value::Object(ref map) => map.values().filter_map(|value| value.as_f64()).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn map_numbers<F: FnMut(f64) -> f64>(params: &Value, mut f: F) -> Value {
+    match *params {
+        Value::Object(ref map) => {
+            let mut mapped = Map::new();
+            for (key, value) in map {
+                let value = match *value {
+                    Value::Number(ref number) if number.is_f64() => {
+                        let x = f(number.as_f64().unwrap());
+                        Number::from_f64(x).map_or(value.clone(), Value::Number)
+                    }
+                    Value::Number(ref number) => {
+                        let x = f(number.as_f64().unwrap()).round() as i64;
+                        Value::Number(Number::from(x))
+                    }
+                    _ => value.clone(),
+                };
+                mapped.insert(key.clone(), value);
+            }
+            Value::Object(mapped)
+        }
+        _ => params.clone(),
+    }
+}
+
+impl fmt::Display for Optimizer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} variants, {} games each against {}, first seed {}",
+            self.population,
+            self.games,
+            self.opponents.join(", "),
+            self.seed
+        )?;
+        writeln!(
+            f,
+            "{: >10} {: >10} {: >10} {: >10} {: >8}",
+            "Generation",
+            "Current",
+            "Best",
+            "Mean",
+            "Step"
+        )?;
+        for (index, generation) in self.history.iter().enumerate() {
+            writeln!(
+                f,
+                "{: >10} {: >10.3} {: >10.3} {: >10.3} {: >8.3}",
+                index + 1,
+                generation.current,
+                generation.best,
+                generation.mean,
+                generation.step
+            )?;
+        }
+        if let Some(points) = self.best_points() {
+            writeln!(f, "Best weights played took {:.3} points per round", points)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perturbs_numbers_only() {
+        let params = serde_json::to_value(&DefensiveParams::default()).unwrap();
+        let mut rng = StdRng::from_seed(&[3]);
+        let variant = perturb(&params, 0.5, &mut rng);
+
+        assert_ne!(params, variant);
+        assert!(variant["heart_pass_bonus"].is_i64());
+        assert!(variant["points_scale"].is_f64());
+        assert!(serde_json::from_value::<DefensiveParams>(variant.clone()).is_ok());
+        assert_eq!(numbers(&variant), numbers(&average(&params, &[&variant, &variant])));
+    }
+
+    #[test]
+    fn moves_only_towards_better_variants() {
+        let mut optimizer =
+            Optimizer::new(DefensiveParams::default(), &["simple"], 2, 1, 5).unwrap();
+        optimizer.run(3).unwrap();
+
+        assert_eq!(3, optimizer.history.len());
+        let fewest = optimizer
+            .history
+            .iter()
+            .map(|generation| generation.current.min(generation.best))
+            .fold(f64::INFINITY, f64::min);
+        assert_eq!(Some(fewest), optimizer.best_points());
+        let improved = optimizer.history.iter().any(|generation| {
+            generation.best < generation.current
+        });
+        assert_eq!(improved, optimizer.params() != &DefensiveParams::default());
+        assert!(format!("{}", optimizer).contains("Generation"));
+    }
+
+    #[test]
+    fn keeps_points_scaled() {
+        let mut params = serde_json::to_value(&DefensiveParams::default()).unwrap();
+        params["points_scale"] = Value::Number(Number::from_f64(1.5).unwrap());
+        let mut rng = StdRng::from_seed(&[3]);
+        for _ in 0..20 {
+            let variant = perturb(&params, 2.0, &mut rng);
+            assert!(variant["points_scale"].as_f64().unwrap() >= MIN_POINTS_SCALE);
+            assert!(serde_json::from_value::<DefensiveParams>(variant).is_ok());
+        }
+    }
+
+    #[test]
+    fn checks_opponents() {
+        let params = DefensiveParams::default();
+        assert!(Optimizer::new(params.clone(), &[], 4, 1, 0).is_err());
+        assert!(Optimizer::new(params.clone(), &["clever"], 4, 1, 0).is_err());
+        assert!(Optimizer::new(params, &["simple"], 1, 1, 0).is_err());
+    }
+}
@@ -0,0 +1,444 @@
+use archive;
+use card::CardSet;
+use deal::Deal;
+use deal::DealCard;
+use error::Result;
+use game_status::GameStatus;
+use game_status::PlayerName;
+use game_status::RoundParameters;
+use inference::Inference;
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+
+const COLUMN_WIDTH: usize = 12;
+const DEAL_COUNT: usize = 13;
+
+/// A game rebuilt from the snapshots the client logs while playing, deal by deal.
+pub struct Replay {
+    game_id: String,
+    me: Option<PlayerName>,
+    rounds: Vec<RoundReplay>,
+}
+
+/// A round of a replay, with the deals played as far as they were logged.
+pub struct RoundReplay {
+    pub round_id: u32,
+    pub players: Vec<PlayerName>,
+    pub parameters: RoundParameters,
+    pub deals: Vec<Deal>,
+    pub passed: CardSet,
+    pub received: CardSet,
+    passing: Option<String>,
+    pub finished: bool,
+}
+
+impl Replay {
+    /// Reads the snapshots of the game from the log directory, archived or not. The last
+    /// snapshot of each round has every deal played up to that point.
+    pub fn load<P: AsRef<Path>>(log_dir: P, game_id: &str) -> Result<Replay> {
+        let mut statuses = BTreeMap::new();
+        for ((round_id, _), game_status) in archive::read_game(log_dir, game_id)? {
+            statuses
+                .entry(round_id)
+                .or_insert_with(Vec::new)
+                .push(game_status);
+        }
+        let me = statuses
+            .values()
+            .flat_map(|round| round.iter())
+            .filter(|game_status| game_status.is_my_turn)
+            .flat_map(|game_status| game_status.game_players.iter())
+            .find(|player| player.has_turn)
+            .map(|player| player.team_name.clone());
+
+        Ok(Replay {
+            game_id: game_id.to_owned(),
+            rounds: statuses
+                .into_iter()
+                .map(|(round_id, round)| {
+                    RoundReplay::new(round_id, round.last().unwrap(), me.as_ref())
+                })
+                .collect(),
+            me: me,
+        })
+    }
+
+    /// The player the snapshots were logged for, if it was ever their turn.
+    pub fn me(&self) -> Option<&PlayerName> {
+        self.me.as_ref()
+    }
+
+    pub fn rounds(&self) -> &[RoundReplay] {
+        &self.rounds
+    }
+
+    /// Writes the game deal by deal, calling `next` after each deal. Stops early when it
+    /// returns false.
+    pub fn show<W, F>(&self, out: &mut W, mut next: F) -> Result<()>
+    where
+        W: Write,
+        F: FnMut() -> bool,
+    {
+        writeln!(out, "Game {}", self.game_id)?;
+        let mut game_scores = BTreeMap::new();
+        for round in &self.rounds {
+            writeln!(out)?;
+            round.write_header(out)?;
+            let mut points = BTreeMap::new();
+            for deal in &round.deals {
+                round.write_deal(out, deal, &mut points)?;
+                if !next() {
+                    return Ok(());
+                }
+            }
+            round.write_footer(out, &mut game_scores)?;
+        }
+        Ok(())
+    }
+}
+
+impl RoundReplay {
+    pub fn new(round_id: u32, game_status: &GameStatus, me: Option<&PlayerName>) -> RoundReplay {
+        let mut deals = game_status.game_deals.clone();
+        let in_progress_deal = game_status.in_progress_deal.iter().find(|deal| {
+            deals.iter().all(|other| other.deal_number != deal.deal_number)
+        });
+        if let Some(deal) = in_progress_deal {
+            let last_deal = me.and_then(|me| Self::complete_last_deal(game_status, deal, me));
+            if let Some(deal) = last_deal {
+                deals.push(deal);
+            } else if !deal.deal_cards.is_empty() {
+                deals.push(deal.clone());
+            }
+        }
+        let passing = me.and_then(|me| {
+            game_status.pass_receiver(me).map(|receiver| {
+                let mut passing = format!(
+                    "Passed {} {}, to {}",
+                    game_status.cards_passed_by_me,
+                    game_status.pass_direction(),
+                    receiver
+                );
+                if let Some(giver) = game_status.pass_giver(me) {
+                    passing += &format!(
+                        "; received {} from {}",
+                        game_status.cards_passed_to_me,
+                        giver
+                    );
+                }
+                passing
+            })
+        });
+        RoundReplay {
+            round_id: round_id,
+            players: game_status
+                .game_players
+                .iter()
+                .map(|player| player.team_name.clone())
+                .collect(),
+            parameters: game_status.round_parameters.clone(),
+            passed: game_status.cards_passed_by_me,
+            received: game_status.cards_passed_to_me,
+            finished: deals.len() == DEAL_COUNT &&
+                deals.iter().all(|deal| deal.deal_winner.is_some()),
+            deals: deals,
+            passing: passing,
+        }
+    }
+
+    /// Snapshots are taken when it is our turn, so the last one of a round misses the end of
+    /// the last deal. The cards still to be played there are worked out where possible.
+    fn complete_last_deal(
+        game_status: &GameStatus,
+        deal: &Deal,
+        me: &PlayerName,
+    ) -> Option<Deal> {
+        if game_status.game_deals.len() + 1 != DEAL_COUNT || !game_status.is_my_turn {
+            return None;
+        }
+        let inference = Inference::new(game_status, me);
+        let mut deal = deal.clone();
+        let mut player_name = match deal.deal_cards.first() {
+            Some(deal_card) => deal_card.player_name.clone(),
+            None => me.clone(),
+        };
+        for _ in 0..game_status.game_players.len() {
+            let played = deal.deal_cards
+                .iter()
+                .any(|deal_card| deal_card.player_name == player_name);
+            if !played {
+                let cards = inference.known_cards(&player_name);
+                if cards.len() != 1 || inference.hand_size(&player_name) != 1 {
+                    return None;
+                }
+                deal.deal_cards.push(DealCard {
+                    player_name: player_name.clone(),
+                    card: cards.iter().next().unwrap(),
+                });
+            }
+            player_name = match game_status.game_players.iter().find(|player| {
+                player.team_name == player_name
+            }) {
+                Some(player) => player.left_participant.clone(),
+                None => return None,
+            };
+        }
+
+        let suit = deal.deal_cards[0].card.suit;
+        deal.suit = Some(suit);
+        deal.deal_winner = deal.deal_cards
+            .iter()
+            .filter(|deal_card| deal_card.card.suit == suit)
+            .max_by_key(|deal_card| deal_card.card)
+            .map(|deal_card| deal_card.player_name.clone());
+        Some(deal)
+    }
+
+    fn write_header<W: Write>(&self, out: &mut W) -> Result<()> {
+        writeln!(out, "Round {}", self.round_id)?;
+        if let Some(ref passing) = self.passing {
+            writeln!(out, "{}", passing)?;
+        }
+        write!(out, "{: >4}  {: <w$}", "Deal", "Leader", w = COLUMN_WIDTH)?;
+        for player_name in &self.players {
+            write!(out, " {: <w$}", column(player_name), w = COLUMN_WIDTH)?;
+        }
+        writeln!(out, " {: <w$} {: >6}", "Winner", "Points", w = COLUMN_WIDTH)?;
+        Ok(())
+    }
+
+    /// One line per deal: the card each player played, with the winning card starred and
+    /// their points for the round so far.
+    fn write_deal<W: Write>(
+        &self,
+        out: &mut W,
+        deal: &Deal,
+        points: &mut BTreeMap<PlayerName, i32>,
+    ) -> Result<()> {
+        let deal_points = deal.deal_cards
+            .iter()
+            .map(|deal_card| self.parameters.points(&deal_card.card))
+            .sum::<i32>();
+        if let Some(ref winner) = deal.deal_winner {
+            *points.entry(winner.clone()).or_insert(0) += deal_points;
+        }
+
+        let leader = deal.deal_cards.first().map(|deal_card| &deal_card.player_name);
+        write!(
+            out,
+            "{: >4}  {: <w$}",
+            deal.deal_number,
+            leader.map(column).unwrap_or_default(),
+            w = COLUMN_WIDTH
+        )?;
+        for player_name in &self.players {
+            let cell = deal.deal_cards
+                .iter()
+                .find(|deal_card| &deal_card.player_name == player_name)
+                .map(|deal_card| {
+                    let won = deal.deal_winner.as_ref() == Some(player_name);
+                    format!(
+                        "{}{} {: >3}",
+                        deal_card.card,
+                        if won { "*" } else { " " },
+                        points.get(player_name).cloned().unwrap_or_default()
+                    )
+                })
+                .unwrap_or_default();
+            write!(out, " {: <w$}", cell, w = COLUMN_WIDTH)?;
+        }
+        writeln!(
+            out,
+            " {: <w$} {: >6}",
+            deal.deal_winner.as_ref().map(column).unwrap_or_default(),
+            deal_points,
+            w = COLUMN_WIDTH
+        )?;
+        Ok(())
+    }
+
+    fn write_footer<W: Write>(
+        &self,
+        out: &mut W,
+        game_scores: &mut BTreeMap<PlayerName, i32>,
+    ) -> Result<()> {
+        if !self.finished {
+            writeln!(out, "Round not finished")?;
+            return Ok(());
+        }
+        if let Some(shooter) = self.shooter() {
+            writeln!(out, "{} shot the moon", shooter)?;
+        }
+
+        write!(out, "{: >4}  {: <w$}", "", "Round", w = COLUMN_WIDTH)?;
+        for player_name in &self.players {
+            let score = self.score(player_name);
+            *game_scores.entry(player_name.clone()).or_insert(0) += score;
+            write!(out, " {: >w$}", score, w = COLUMN_WIDTH)?;
+        }
+        writeln!(out)?;
+        write!(out, "{: >4}  {: <w$}", "", "Game", w = COLUMN_WIDTH)?;
+        for player_name in &self.players {
+            write!(out, " {: >w$}", game_scores[player_name], w = COLUMN_WIDTH)?;
+        }
+        writeln!(out)?;
+        Ok(())
+    }
+
+    /// Points in the cards the player took.
+    pub fn points(&self, player_name: &PlayerName) -> i32 {
+        self.deals
+            .iter()
+            .filter(|deal| deal.deal_winner.as_ref() == Some(player_name))
+            .flat_map(|deal| deal.deal_cards.iter())
+            .map(|deal_card| self.parameters.points(&deal_card.card))
+            .sum()
+    }
+
+    /// Points for the round once a moon shot is accounted for: the shooter gives up the
+    /// points in the deck and everyone else takes them.
+    pub fn score(&self, player_name: &PlayerName) -> i32 {
+        let points = self.points(player_name);
+        let moon_points = self.parameters
+            .card_points
+            .values()
+            .filter(|&&points| points > 0)
+            .sum::<i32>();
+        match self.shooter() {
+            Some(shooter) if shooter == player_name => points - moon_points,
+            Some(_) => points + moon_points,
+            None => points,
+        }
+    }
+
+    /// The player who took every card worth points, if anyone did.
+    pub fn shooter(&self) -> Option<&PlayerName> {
+        let mut winners = self.deals
+            .iter()
+            .filter(|deal| {
+                deal.deal_cards.iter().any(|deal_card| {
+                    self.parameters.points(&deal_card.card) > 0
+                })
+            })
+            .filter_map(|deal| deal.deal_winner.as_ref())
+            .collect::<BTreeSet<_>>()
+            .into_iter();
+        match (winners.next(), winners.next()) {
+            (Some(shooter), None) => Some(shooter),
+            _ => None,
+        }
+    }
+}
+
+fn column(player_name: &PlayerName) -> String {
+    format!("{}", player_name).chars().take(COLUMN_WIDTH).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine;
+    use engine::Round;
+    use game_status::GameInstanceState;
+    use game_status::HeartsGameInstanceState;
+    use game_status::dto::GameStatusDto;
+
+    use serde_json;
+
+    use std::env;
+    use std::fs;
+    use std::fs::File;
+    use std::process;
+
+    /// Plays a test round and logs snapshots the way the client does, for the player who plays
+    /// last in the last deal. Returns the round.
+    fn log_round(log_dir: &Path, game_id: &str) -> (Round, PlayerName) {
+        let mut round = engine::test_round(engine::round_parameters(1), 5);
+        let mut snapshots = Vec::new();
+        engine::play_test_round(&mut round, |round, player_name| {
+            let status = round.game_status(game_id, GameInstanceState::Running, player_name);
+            let deal_number = match round.game_state() {
+                HeartsGameInstanceState::Passing => 0,
+                _ => status.game_deals.len() as u32 + 1,
+            };
+            snapshots.push((player_name.clone(), deal_number, GameStatusDto::from(&status)));
+        });
+
+        let me = round
+            .game_status(game_id, GameInstanceState::Finished, &engine::test_players()[0])
+            .game_deals
+            .last()
+            .and_then(|deal| deal.deal_cards.last())
+            .map(|deal_card| deal_card.player_name.clone())
+            .unwrap();
+        let game_dir = log_dir.join(game_id);
+        fs::create_dir_all(&game_dir).unwrap();
+        for (player_name, deal_number, dto) in snapshots {
+            if player_name == me {
+                let file_name = game_dir.join(format!("01-{:02}.json", deal_number));
+                let mut file = File::create(file_name).unwrap();
+                file.write_all(serde_json::to_string(&dto).unwrap().as_bytes())
+                    .unwrap();
+            }
+        }
+        (round, me)
+    }
+
+    #[test]
+    fn replays_logged_round() {
+        let log_dir = env::temp_dir().join(format!("hearts-replay-test-{}", process::id()));
+        let (round, me) = log_round(&log_dir, "replayed");
+        let replay = Replay::load(&log_dir, "replayed").unwrap();
+        fs::remove_dir_all(&log_dir).unwrap();
+
+        assert_eq!(1, replay.rounds.len());
+        let replayed = &replay.rounds[0];
+        let game_status = round.game_status("replayed", GameInstanceState::Finished, &me);
+        assert_eq!(game_status.game_deals, replayed.deals);
+        assert!(replayed.finished);
+        assert!(replayed.passing.as_ref().unwrap().contains("to the right"));
+
+        let mut output = Vec::new();
+        replay.show(&mut output, || true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let round_line = output.lines().find(|line| line.contains("Round  ")).unwrap();
+        let scores = round_line
+            .split_whitespace()
+            .skip(1)
+            .map(|score| score.parse::<i32>().unwrap())
+            .collect::<Vec<_>>();
+        let expected = replayed
+            .players
+            .iter()
+            .map(|player_name| round.points(player_name))
+            .collect::<Vec<_>>();
+        assert!(round.shooter().is_none());
+        assert_eq!(expected, scores);
+    }
+
+    #[test]
+    fn stops_when_asked() {
+        let log_dir = env::temp_dir().join(format!("hearts-replay-stop-test-{}", process::id()));
+        log_round(&log_dir, "stopped");
+        let replay = Replay::load(&log_dir, "stopped").unwrap();
+        fs::remove_dir_all(&log_dir).unwrap();
+
+        let mut output = Vec::new();
+        let mut deals = 0;
+        replay
+            .show(&mut output, || {
+                deals += 1;
+                deals < 3
+            })
+            .unwrap();
+        assert_eq!(3, deals);
+    }
+
+    #[test]
+    fn missing_game() {
+        assert!(Replay::load(env::temp_dir(), "no such game").is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use error::Error;
+use error::Result;
+
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How often, and how patiently, a failed request is tried again. The delay doubles after
+/// each attempt, up to a limit. Only errors that might not happen again are retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 4,
+            initial_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// How long to wait after the given failed attempt, counting from 1.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::max_value());
+        self.initial_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// Runs the request until it succeeds, fails for good or runs out of attempts.
+    pub fn run<A, F>(&self, description: &str, request: F) -> Result<A>
+    where
+        F: FnMut() -> Result<A>,
+    {
+        self.run_with(description, Error::is_retryable, None, request)
+    }
+
+    /// Runs a request that changes the game, such as passing or playing cards, retrying it
+    /// only if it cannot have been taken on, and not past the deadline.
+    pub fn submit<A, F>(&self, description: &str, deadline: Instant, request: F) -> Result<A>
+    where
+        F: FnMut() -> Result<A>,
+    {
+        self.run_with(description, Error::is_retryable_submission, Some(deadline), request)
+    }
+
+    fn run_with<A, F>(
+        &self,
+        description: &str,
+        retryable: fn(&Error) -> bool,
+        deadline: Option<Instant>,
+        mut request: F,
+    ) -> Result<A>
+    where
+        F: FnMut() -> Result<A>,
+    {
+        let mut attempt = 1;
+        loop {
+            let delay = self.delay(attempt);
+            let in_time = deadline.map_or(true, |deadline| Instant::now() + delay < deadline);
+            match request() {
+                Err(ref e) if retryable(e) && attempt < self.attempts && in_time => {
+                    warn!(
+                        "{} failed ({}), attempt {} of {}, retrying in {}ms",
+                        description,
+                        e,
+                        attempt,
+                        self.attempts,
+                        delay.as_secs() * 1000 + delay.subsec_nanos() as u64 / 1_000_000
+                    );
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::status::StatusCode;
+
+    use std::io::Error as IoError;
+    use std::io::ErrorKind;
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy {
+            attempts: 3,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(3),
+        }
+    }
+
+    #[test]
+    fn delays_double_up_to_limit() {
+        let policy = RetryPolicy::default();
+        assert_eq!(Duration::from_millis(200), policy.delay(1));
+        assert_eq!(Duration::from_millis(400), policy.delay(2));
+        assert_eq!(Duration::from_millis(1600), policy.delay(4));
+        assert_eq!(Duration::from_secs(2), policy.delay(5));
+        assert_eq!(Duration::from_secs(2), policy.delay(40));
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let mut attempts = 0;
+        let result = policy().run("Test", || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(Error::Status(StatusCode::ServiceUnavailable, String::new()))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(3, result.unwrap());
+
+        attempts = 0;
+        let result: Result<()> = policy().run("Test", || {
+            attempts += 1;
+            Err(Error::Status(StatusCode::BadGateway, "down".to_owned()))
+        });
+        assert_eq!(3, attempts);
+        assert_eq!("Server responded 502 Bad Gateway: down", format!("{}", result.unwrap_err()));
+    }
+
+    #[test]
+    fn retries_submissions_only_if_not_taken_on() {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let refused = || IoError::new(ErrorKind::ConnectionRefused, "refused");
+        let mut attempts = 0;
+        let result = policy().submit("Test", deadline, || {
+            attempts += 1;
+            if attempts < 3 {
+                Err(Error::Io(refused()))
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(3, result.unwrap());
+
+        for error in vec![
+            Error::Io(IoError::new(ErrorKind::ConnectionReset, "reset")),
+            Error::Status(StatusCode::BadGateway, String::new()),
+        ]
+        {
+            assert!(error.is_retryable());
+            assert!(!error.is_retryable_submission());
+        }
+        assert!(Error::Status(StatusCode::ServiceUnavailable, String::new())
+            .is_retryable_submission());
+    }
+
+    #[test]
+    fn stops_retrying_at_the_deadline() {
+        let policy = RetryPolicy {
+            attempts: 10,
+            initial_delay: Duration::from_millis(20),
+            max_delay: Duration::from_millis(20),
+        };
+        let started = Instant::now();
+        let mut attempts = 0;
+        let result: Result<()> =
+            policy.submit("Test", started + Duration::from_millis(50), || {
+                attempts += 1;
+                Err(Error::Status(StatusCode::ServiceUnavailable, String::new()))
+            });
+        assert!(result.is_err());
+        assert!(attempts >= 2 && attempts <= 3);
+        assert!(started.elapsed() < Duration::from_millis(150));
+    }
+
+    #[test]
+    fn does_not_retry_rejected_requests() {
+        let mut attempts = 0;
+        let result: Result<()> = policy().run("Test", || {
+            attempts += 1;
+            Err(Error::Status(StatusCode::Unauthorized, String::new()))
+        });
+        assert_eq!(1, attempts);
+        assert!(!result.unwrap_err().is_retryable());
+        assert!(!Error::game(Some("Not your turn")).is_retryable());
+    }
+}
@@ -0,0 +1,212 @@
+use card::Card;
+use card::CardSet;
+use card::Rank;
+use card::Suit;
+use deal::Deal;
+use game_status::RoundParameters;
+
+use std::iter;
+
+/// The cards in `hand` that may legally be played next, given the deals already played
+/// this round and the one in progress:
+///
+/// * the first deal must be led with the two of clubs,
+/// * players must follow suit when they can,
+/// * cards worth points may not be played on the first deal unless nothing else can be,
+/// * hearts may not be led until a heart has been played, unless nothing else can be.
+pub fn legal_plays(
+    hand: CardSet,
+    game_deals: &[Deal],
+    in_progress_deal: Option<&Deal>,
+    round_parameters: &RoundParameters,
+) -> CardSet {
+    let played_cards = in_progress_deal
+        .map(|deal| deal.deal_cards.as_slice())
+        .unwrap_or_default();
+    let first_deal = game_deals.iter().all(|deal| deal.deal_cards.is_empty());
+    let lead_suit = if played_cards.is_empty() {
+        None
+    } else {
+        in_progress_deal.and_then(|deal| deal.suit)
+    };
+
+    let candidates = match lead_suit {
+        Some(suit) => {
+            let following = hand.suit(suit);
+            if following.is_empty() { hand } else { following }
+        }
+        None => {
+            let two_of_clubs = Rank::Two.of(Suit::Club);
+            if first_deal && hand.contains(&two_of_clubs) {
+                return iter::once(two_of_clubs).collect();
+            }
+            let hearts_broken = game_deals
+                .iter()
+                .flat_map(|deal| deal.deal_cards.iter())
+                .any(|deal_card| deal_card.card.suit == Suit::Heart);
+            if hearts_broken {
+                hand
+            } else {
+                prefer(hand, hand.difference(hand.suit(Suit::Heart)))
+            }
+        }
+    };
+
+    if first_deal {
+        let pointless = candidates
+            .iter()
+            .filter(|card| round_parameters.points(card) <= 0)
+            .collect();
+        prefer(candidates, pointless)
+    } else {
+        candidates
+    }
+}
+
+/// The legal play to fall back on when the strategy cannot choose one: the card worth the
+/// fewest points, the lowest of those.
+pub fn fallback_play(legal_plays: CardSet, round_parameters: &RoundParameters) -> Option<Card> {
+    legal_plays
+        .iter()
+        .min_by_key(|card| (round_parameters.points(card), card.rank))
+}
+
+/// The cards to fall back on passing when the strategy cannot choose them: the ones worth the
+/// most points, the highest of those.
+pub fn fallback_pass(hand: CardSet, round_parameters: &RoundParameters) -> Vec<Card> {
+    let mut cards = hand.iter().collect::<Vec<_>>();
+    cards.sort_by_key(|card| (round_parameters.points(card), card.rank));
+    cards.into_iter().rev().take(round_parameters.cards_to_pass()).collect()
+}
+
+/// The preferred candidates, unless there are none.
+fn prefer(candidates: CardSet, preferred: CardSet) -> CardSet {
+    if preferred.is_empty() {
+        candidates
+    } else {
+        preferred
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::Rank::*;
+    use card::Suit::*;
+    use deal::DealCard;
+    use game_status::PlayerName;
+
+    use std::collections::BTreeMap;
+
+    fn parameters() -> RoundParameters {
+        let mut card_points = BTreeMap::new();
+        for rank in Rank::all() {
+            card_points.insert(rank.of(Heart), 1);
+        }
+        card_points.insert(Queen.of(Spade), 13);
+        RoundParameters {
+            card_points: card_points,
+            ..RoundParameters::default()
+        }
+    }
+
+    fn deal(cards: Vec<Card>) -> Deal {
+        Deal {
+            deal_number: 1,
+            initiator: Some(PlayerName::new("North")),
+            suit: cards.first().map(|card| card.suit),
+            deal_cards: cards
+                .into_iter()
+                .map(|card| {
+                    DealCard {
+                        player_name: PlayerName::new("North"),
+                        card: card,
+                    }
+                })
+                .collect(),
+            deal_winner: None,
+        }
+    }
+
+    fn hand(cards: Vec<Card>) -> CardSet {
+        cards.into_iter().collect()
+    }
+
+    fn legal(hand: &CardSet, game_deals: &[Deal], in_progress_deal: Option<&Deal>) -> Vec<Card> {
+        legal_plays(*hand, game_deals, in_progress_deal, &parameters())
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn must_lead_two_of_clubs() {
+        let hand = hand(vec![Two.of(Club), Ace.of(Spade), Three.of(Diamond)]);
+        assert_eq!(vec![Two.of(Club)], legal(&hand, &[], Some(&deal(vec![]))));
+    }
+
+    #[test]
+    fn must_follow_suit() {
+        let hand = hand(vec![Four.of(Club), Ace.of(Spade), Ten.of(Club)]);
+        let first = deal(vec![Two.of(Club)]);
+        assert_eq!(
+            vec![Four.of(Club), Ten.of(Club)],
+            legal(&hand, &[], Some(&first))
+        );
+    }
+
+    #[test]
+    fn no_points_on_first_deal() {
+        let hand = hand(vec![Queen.of(Spade), Ace.of(Heart), Three.of(Diamond)]);
+        let first = deal(vec![Two.of(Club)]);
+        assert_eq!(vec![Three.of(Diamond)], legal(&hand, &[], Some(&first)));
+    }
+
+    #[test]
+    fn points_on_first_deal_when_nothing_else() {
+        let hand = hand(vec![Queen.of(Spade), Ace.of(Heart)]);
+        let first = deal(vec![Two.of(Club)]);
+        assert_eq!(
+            vec![Queen.of(Spade), Ace.of(Heart)],
+            legal(&hand, &[], Some(&first))
+        );
+    }
+
+    #[test]
+    fn cannot_lead_hearts_until_broken() {
+        let hand = hand(vec![Four.of(Heart), Ace.of(Spade)]);
+        let history = vec![deal(vec![Two.of(Club), Three.of(Club)])];
+        assert_eq!(vec![Ace.of(Spade)], legal(&hand, &history, None));
+
+        let broken = vec![deal(vec![Two.of(Club), Three.of(Heart)])];
+        assert_eq!(
+            vec![Ace.of(Spade), Four.of(Heart)],
+            legal(&hand, &broken, None)
+        );
+    }
+
+    #[test]
+    fn lead_hearts_when_nothing_else() {
+        let hand = hand(vec![Four.of(Heart), Ace.of(Heart)]);
+        let history = vec![deal(vec![Two.of(Club), Three.of(Club)])];
+        assert_eq!(
+            vec![Four.of(Heart), Ace.of(Heart)],
+            legal(&hand, &history, Some(&deal(vec![])))
+        );
+    }
+
+    #[test]
+    fn falls_back_on_fewest_points() {
+        let hand = hand(vec![Queen.of(Spade), Two.of(Heart), Ace.of(Club), Three.of(Diamond)]);
+        assert_eq!(Some(Three.of(Diamond)), fallback_play(hand, &parameters()));
+        assert_eq!(None, fallback_play(CardSet::new(), &parameters()));
+
+        let passing = RoundParameters {
+            number_of_cards_to_be_passed: 3,
+            ..parameters()
+        };
+        assert_eq!(
+            vec![Queen.of(Spade), Two.of(Heart), Ace.of(Club)],
+            fallback_pass(hand, &passing)
+        );
+    }
+}
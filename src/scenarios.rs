@@ -0,0 +1,261 @@
+use card::Card;
+use error::Result;
+use game_status::GameStatus;
+use game_status::PlayerName;
+use game_status::dto::GameStatusDto;
+use strategy::Registry;
+
+use serde_json;
+use serde_json::Value;
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// The manifest of expected cards kept alongside the scenarios, by scenario name.
+pub const MANIFEST: &'static str = "expected.json";
+
+/// Runs a strategy over a directory of logged game states and checks the card it chooses in
+/// each one against the card expected in the manifest.
+pub struct Scenarios {
+    strategy_name: String,
+    results: Vec<ScenarioResult>,
+}
+
+struct ScenarioResult {
+    name: String,
+    chosen: Option<Card>,
+    expected: Option<Card>,
+}
+
+impl Scenarios {
+    /// Scenarios where it is not the player's turn to play are skipped.
+    pub fn run<P: AsRef<Path>>(
+        dir: P,
+        manifest: Option<PathBuf>,
+        strategy_name: &str,
+        parameters: &Value,
+    ) -> Result<Scenarios> {
+        let registry = Registry::standard();
+        let dir = dir.as_ref();
+        let manifest = manifest.unwrap_or_else(|| dir.join(MANIFEST));
+        let expected_cards = if manifest.exists() {
+            read_manifest(&manifest)?
+        } else {
+            BTreeMap::new()
+        };
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            let scenario = path.extension().map_or(false, |extension| {
+                extension == "json" || extension == "txt"
+            });
+            if scenario && path != manifest {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        let mut results = Vec::new();
+        for path in paths {
+            let name = path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let game_status = read_game_status(&path)?;
+            let chosen = match game_status.game_players.iter().find(|player| player.has_turn) {
+                Some(player) if game_status.is_my_turn => {
+                    let player_name = player.team_name.clone();
+                    let mut strategy = registry.create(strategy_name, player_name, parameters)?;
+                    Some(strategy.play_card(&game_status))
+                }
+                _ => None,
+            };
+            results.push(ScenarioResult {
+                expected: expected_cards.get(&name).cloned(),
+                name: name,
+                chosen: chosen,
+            });
+        }
+        Ok(Scenarios {
+            strategy_name: strategy_name.to_owned(),
+            results: results,
+        })
+    }
+
+    /// The number of scenarios where the strategy did not play the expected card.
+    pub fn failures(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.outcome() == "fail")
+            .count()
+    }
+}
+
+impl ScenarioResult {
+    fn outcome(&self) -> &'static str {
+        match (self.chosen, self.expected) {
+            (None, _) => "skipped",
+            (Some(_), None) => "-",
+            (Some(chosen), Some(expected)) if chosen == expected => "pass",
+            _ => "fail",
+        }
+    }
+}
+
+impl fmt::Display for Scenarios {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = self.results
+            .iter()
+            .map(|result| result.name.chars().count())
+            .max()
+            .unwrap_or_default();
+        writeln!(f, "Strategy {}", self.strategy_name)?;
+        writeln!(
+            f,
+            "{: <w$} {: >6} {: >8} {: >7}",
+            "Scenario",
+            "Chosen",
+            "Expected",
+            "Result",
+            w = width
+        )?;
+        let card = |card: Option<Card>| card.map(|card| format!("{}", card)).unwrap_or_default();
+        for result in &self.results {
+            writeln!(
+                f,
+                "{: <w$} {: >6} {: >8} {: >7}",
+                result.name,
+                card(result.chosen),
+                card(result.expected),
+                result.outcome(),
+                w = width
+            )?;
+        }
+        let count = |outcome| {
+            self.results
+                .iter()
+                .filter(|result| result.outcome() == outcome)
+                .count()
+        };
+        writeln!(
+            f,
+            "{} passed, {} failed, {} without an expected card, {} skipped",
+            count("pass"),
+            count("fail"),
+            count("-"),
+            count("skipped")
+        )
+    }
+}
+
+/// Reads a game state as the server sends it or, from a `.txt` file, written out by hand.
+pub fn read_game_status(path: &Path) -> Result<GameStatus> {
+    let mut string = String::new();
+    File::open(path)?.read_to_string(&mut string)?;
+    if path.extension().map_or(false, |extension| extension == "txt") {
+        return GameStatus::from_str(&string);
+    }
+    let dto: GameStatusDto = serde_json::from_str(&string)?;
+    GameStatus::try_from(dto)
+}
+
+/// Keeps a game state seen by the given player as a `.txt` scenario in the directory, with the
+/// card expected to be played in it added to the manifest there, replacing any scenario of the
+/// same name. Returns the scenario's path.
+pub fn save<P: AsRef<Path>>(
+    dir: P,
+    name: &str,
+    game_status: &GameStatus,
+    me: &PlayerName,
+    expected: Card,
+) -> Result<PathBuf> {
+    let dir = dir.as_ref();
+    fs::DirBuilder::new().recursive(true).create(dir)?;
+    let path = dir.join(format!("{}.txt", name));
+    let deal_number = game_status
+        .in_progress_deal
+        .as_ref()
+        .map_or(0, |deal| deal.deal_number);
+    let mut file = File::create(&path)?;
+    writeln!(
+        file,
+        "# Saved from play in round {}, deal {}.",
+        game_status.current_round_id,
+        deal_number
+    )?;
+    file.write_all(game_status.to_scenario(me).as_bytes())?;
+
+    let manifest = dir.join(MANIFEST);
+    let mut cards = if manifest.exists() {
+        read_manifest(&manifest)?
+    } else {
+        BTreeMap::new()
+    };
+    cards.insert(name.to_owned(), expected);
+    let codes = cards
+        .into_iter()
+        .map(|(name, card)| (name, card.code()))
+        .collect::<BTreeMap<_, _>>();
+    writeln!(File::create(manifest)?, "{}", serde_json::to_string_pretty(&codes)?)?;
+    Ok(path)
+}
+
+fn read_manifest(path: &Path) -> Result<BTreeMap<String, Card>> {
+    let mut string = String::new();
+    File::open(path)?.read_to_string(&mut string)?;
+    let cards: BTreeMap<String, String> = serde_json::from_str(&string)?;
+    cards
+        .into_iter()
+        .map(|(name, card)| Card::from_str(&card).map(|card| (name, card)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::Rank;
+    use card::Suit;
+
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn defensive_matches_manifest() {
+        let scenarios =
+            Scenarios::run("samples/scenarios", None, "defensive", &Value::Null).unwrap();
+        let output = format!("{}", scenarios);
+
+        assert_eq!(49, scenarios.results.len());
+        assert_eq!(1, scenarios.failures());
+        assert!(output.contains("46 passed, 1 failed, 0 without an expected card, 2 skipped"));
+    }
+
+    #[test]
+    fn saves_scenarios_to_check() {
+        let game_status = read_game_status(Path::new("samples/scenarios/normal 1.json")).unwrap();
+        let me = PlayerName::new("Derek Williams");
+        let dir = env::temp_dir().join(format!("hearts-saved-scenarios-{}", process::id()));
+        let path = save(&dir, "saved", &game_status, &me, Rank::Jack.of(Suit::Diamond)).unwrap();
+        let saved = read_game_status(&path).unwrap();
+        let scenarios = Scenarios::run(&dir, None, "defensive", &Value::Null).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(game_status.legal_plays(), saved.legal_plays());
+        assert_eq!(0, scenarios.failures());
+        assert!(format!("{}", scenarios).contains("1 passed"));
+    }
+
+    #[test]
+    fn unknown_strategy() {
+        let strategy_name = "no such strategy";
+        assert!(Scenarios::run("samples/scenarios", None, strategy_name, &Value::Null).is_err());
+    }
+}
@@ -0,0 +1,145 @@
+mod table;
+
+use server::table::Table;
+
+use card::Card;
+use card::dto::CardDto;
+use game_status::PlayerName;
+use game_status::dto::GameStatusDto;
+use hearts_client::GameResponse;
+use error::Error;
+use error::Result;
+
+use std::convert::TryFrom;
+use std::io::Read;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+use hyper::method::Method;
+use hyper::server::Handler;
+use hyper::server::Request;
+use hyper::server::Response;
+use hyper::server::Server;
+use hyper::status::StatusCode;
+use hyper::uri::RequestUri;
+use hyper::header;
+
+use serde_json;
+
+const BASE_PATH: &'static str = "/api/participant";
+
+/// Stand-in for the competition server, speaking the same participant protocol so that
+/// `HeartsClient` can be run locally. Empty seats are filled with dummy players.
+pub struct HeartsServer {
+    table: Arc<Mutex<Table>>,
+}
+
+impl HeartsServer {
+    pub fn new(join_timeout: Duration) -> HeartsServer {
+        HeartsServer { table: Arc::new(Mutex::new(Table::new(join_timeout))) }
+    }
+
+    pub fn run(self, address: &str) -> Result<()> {
+        let _listening = Server::http(address)?.handle(ParticipantHandler {
+            table: self.table.clone(),
+        })?;
+        info!("Listening on http://{}{}", address, BASE_PATH);
+        loop {
+            self.table.lock().unwrap().tick(Instant::now());
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+struct ParticipantHandler {
+    table: Arc<Mutex<Table>>,
+}
+
+impl ParticipantHandler {
+    fn respond(&self, request: &mut Request, player_name: &PlayerName) -> Result<String> {
+        let path = match request.uri {
+            RequestUri::AbsolutePath(ref path) => path.clone(),
+            _ => String::new(),
+        };
+        // The body is read and parsed before the table is locked, so that a slow client does
+        // not hold up the other seats.
+        let body = Self::body(request)?;
+        let now = Instant::now();
+        match (&request.method, path.trim_left_matches(BASE_PATH)) {
+            (&Method::Get, "/gamestatus") => {
+                let game_status = self.table.lock().unwrap().game_status(player_name);
+                Ok(serde_json::to_string(&GameStatusDto::from(&game_status))?)
+            }
+            (&Method::Post, "/join") => {
+                let mut table = self.table.lock().unwrap();
+                table.join(player_name, now).map(|_| "true".to_owned())
+            }
+            (&Method::Post, "/passcards") => {
+                let cards = serde_json::from_str::<Vec<CardDto>>(&body)?
+                    .into_iter()
+                    .map(Card::try_from)
+                    .collect::<Result<_>>()?;
+                let mut table = self.table.lock().unwrap();
+                table.pass_cards(player_name, cards).map(
+                    |_| "true".to_owned(),
+                )
+            }
+            (&Method::Post, "/playcard") => {
+                let card = Card::try_from(serde_json::from_str::<CardDto>(&body)?)?;
+                let mut table = self.table.lock().unwrap();
+                table.play_card(player_name, card, now).map(
+                    |_| "true".to_owned(),
+                )
+            }
+            (method, path) => Err(Error::game(
+                Some(format!("Unknown request: {} {}", method, path)),
+            )),
+        }
+    }
+
+    fn body(request: &mut Request) -> Result<String> {
+        let mut body = String::new();
+        request.read_to_string(&mut body)?;
+        Ok(body)
+    }
+
+    fn player_name(request: &Request) -> Option<PlayerName> {
+        request
+            .headers
+            .get::<header::Authorization<header::Basic>>()
+            .map(|authorization| PlayerName::new(authorization.username.clone()))
+    }
+}
+
+impl Handler for ParticipantHandler {
+    fn handle(&self, mut request: Request, mut response: Response) {
+        let player_name = match Self::player_name(&request) {
+            Some(player_name) => player_name,
+            None => {
+                *response.status_mut() = StatusCode::Unauthorized;
+                return;
+            }
+        };
+
+        if request.method == Method::Head {
+            return;
+        }
+
+        let game_response = match self.respond(&mut request, &player_name) {
+            Ok(data) => GameResponse::data(data),
+            Err(e) => {
+                warn!("{}: {}", player_name, e);
+                GameResponse::fault(format!("{}", e))
+            }
+        };
+
+        response.headers_mut().set(header::ContentType::json());
+        let body = serde_json::to_string(&game_response).unwrap_or_default();
+        response.send(body.as_bytes()).unwrap_or_else(|e| {
+            error!("Failed to send response: {}", e)
+        });
+    }
+}
@@ -0,0 +1,410 @@
+use card::Card;
+use card::CardSet;
+use engine;
+use engine::Round;
+use error::Error;
+use error::Result;
+use game_status::GameStatus;
+use game_status::GameInstanceState;
+use game_status::RoundState;
+use game_status::HeartsGameInstanceState;
+use game_status::RoundParameters;
+use game_status::GameParticipant;
+use game_status::PlayerName;
+use opponents::Opponents;
+use strategy::CardStrategy;
+use strategy::SimpleCardStrategy;
+
+use rand;
+use rand::Rng;
+
+use std::time::Duration;
+use std::time::Instant;
+
+const FINISHED_GAME_SECONDS: u64 = 10;
+
+pub struct Table {
+    game_id: String,
+    game_state: GameInstanceState,
+    join_timeout: Duration,
+    seats: Vec<Seat>,
+    round: Option<Round>,
+    phase_started: Instant,
+}
+
+struct Seat {
+    player_name: PlayerName,
+    dummy: Option<SimpleCardStrategy>,
+}
+
+impl Table {
+    pub fn new(join_timeout: Duration) -> Table {
+        let mut rng = rand::thread_rng();
+        Table {
+            game_id: format!("{:016x}{:016x}", rng.gen::<u64>(), rng.gen::<u64>()),
+            game_state: GameInstanceState::Open,
+            join_timeout: join_timeout,
+            seats: Vec::new(),
+            round: None,
+            phase_started: Instant::now(),
+        }
+    }
+
+    pub fn join(&mut self, player_name: &PlayerName, now: Instant) -> Result<()> {
+        if self.seated(player_name) {
+            Ok(())
+        } else if self.game_state != GameInstanceState::Open {
+            Err(Error::game(Some("Game is not open")))
+        } else if self.seats.len() >= engine::PLAYER_COUNT {
+            Err(Error::game(Some("Game is full")))
+        } else {
+            if self.seats.is_empty() {
+                self.phase_started = now;
+            }
+            info!("{} joined game {}", player_name, self.game_id);
+            self.seats.push(Seat {
+                player_name: player_name.clone(),
+                dummy: None,
+            });
+            Ok(())
+        }
+    }
+
+    pub fn pass_cards(&mut self, player_name: &PlayerName, cards: Vec<Card>) -> Result<()> {
+        let round = self.running_round(HeartsGameInstanceState::Passing)?;
+        round.pass_cards(player_name, cards)
+    }
+
+    pub fn play_card(&mut self, player_name: &PlayerName, card: Card, now: Instant) -> Result<()> {
+        self.running_round(HeartsGameInstanceState::Dealing)?
+            .play_card(player_name, card)?;
+        self.phase_started = now;
+        Ok(())
+    }
+
+    pub fn game_status(&self, player_name: &PlayerName) -> GameStatus {
+        match self.round {
+            Some(ref round) if self.seated(player_name) => {
+                round.game_status(&self.game_id, self.game_state, player_name)
+            }
+            _ => {
+                GameStatus {
+                    current_game_id: self.game_id.clone(),
+                    current_game_state: self.game_state,
+                    current_round_id: 0,
+                    current_round_state: RoundState::NotStarted,
+                    round_parameters: RoundParameters::default(),
+                    game_state: HeartsGameInstanceState::NotStarted,
+                    game_state_description: engine::describe(
+                        HeartsGameInstanceState::NotStarted,
+                    ).to_owned(),
+                    game_players: self.participants(),
+                    my_initial_hand: CardSet::new(),
+                    cards_passed_by_me: CardSet::new(),
+                    cards_passed_to_me: CardSet::new(),
+                    my_final_hand: CardSet::new(),
+                    my_current_hand: CardSet::new(),
+                    game_deals: Vec::new(),
+                    in_progress_deal: None,
+                    is_my_turn: false,
+                    opponents: Opponents::default(),
+                }
+            }
+        }
+    }
+
+    pub fn tick(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.phase_started);
+        match self.game_state {
+            GameInstanceState::Open => {
+                if self.seats.len() == engine::PLAYER_COUNT ||
+                    (!self.seats.is_empty() && elapsed >= self.join_timeout)
+                {
+                    self.start_game(now);
+                }
+            }
+            GameInstanceState::Running => self.advance_round(elapsed, now),
+            GameInstanceState::Finished |
+            GameInstanceState::Cancelled => {
+                if elapsed >= Duration::from_secs(FINISHED_GAME_SECONDS) {
+                    *self = Table::new(self.join_timeout);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn start_game(&mut self, now: Instant) {
+        let mut dummy_number = 0;
+        while self.seats.len() < engine::PLAYER_COUNT {
+            let player_name = PlayerName::new(format!("Dummy-{}", dummy_number));
+            dummy_number += 1;
+            self.seats.push(Seat {
+                dummy: Some(SimpleCardStrategy::new(player_name.clone())),
+                player_name: player_name,
+            });
+        }
+        info!(
+            "Starting game {} with {}",
+            self.game_id,
+            self.seats
+                .iter()
+                .map(|seat| format!("{}", seat.player_name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        self.game_state = GameInstanceState::Running;
+        self.start_round(1, now);
+    }
+
+    fn start_round(&mut self, round_id: u32, now: Instant) {
+        let seat_order = self.seats
+            .iter()
+            .map(|seat| seat.player_name.clone())
+            .collect();
+        self.round = Some(Round::new(
+            engine::round_parameters(round_id),
+            seat_order,
+            &mut rand::thread_rng(),
+        ));
+        self.phase_started = now;
+    }
+
+    fn advance_round(&mut self, elapsed: Duration, now: Instant) {
+        let (round_id, round_state, game_state, phase_seconds) = match self.round {
+            Some(ref round) => {
+                let parameters = round.parameters();
+                let phase_seconds = match (round.round_state(), round.game_state()) {
+                    (RoundState::Initiated, _) => parameters.initiation_phase_in_seconds,
+                    (_, HeartsGameInstanceState::Passing) => parameters.passing_phase_in_seconds,
+                    (_, HeartsGameInstanceState::Dealing) => parameters.dealing_phase_in_seconds,
+                    _ => parameters.finishing_phase_in_seconds,
+                };
+                (
+                    parameters.round_id,
+                    round.round_state(),
+                    round.game_state(),
+                    phase_seconds,
+                )
+            }
+            None => return,
+        };
+        let timed_out = elapsed >= Duration::from_secs(phase_seconds as u64);
+
+        match (round_state, game_state) {
+            (RoundState::Initiated, _) => {
+                if timed_out {
+                    self.with_round(|round| round.start_passing());
+                    self.phase_started = now;
+                }
+            }
+            (RoundState::Running, HeartsGameInstanceState::Passing) => {
+                self.pass_dummy_cards(timed_out);
+                if self.with_round(|round| round.exchange_cards()) {
+                    self.phase_started = now;
+                }
+            }
+            (RoundState::Running, HeartsGameInstanceState::Dealing) => {
+                if self.play_dummy_card(timed_out) {
+                    self.phase_started = now;
+                }
+            }
+            (RoundState::Finished, _) => {
+                if timed_out {
+                    if round_id < engine::ROUND_COUNT {
+                        self.start_round(round_id + 1, now);
+                    } else {
+                        info!("Game {} finished", self.game_id);
+                        self.game_state = GameInstanceState::Finished;
+                        self.phase_started = now;
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn pass_dummy_cards(&mut self, timed_out: bool) {
+        let pending = self.seats
+            .iter()
+            .filter(|seat| seat.dummy.is_some() || timed_out)
+            .map(|seat| seat.player_name.clone())
+            .filter(|player_name| {
+                self.round.as_ref().map_or(false, |round| {
+                    !round.has_passed(player_name)
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for player_name in pending {
+            let game_status = self.game_status(&player_name);
+            let cards = self.stand_in(&player_name, |strategy| strategy.pass_cards(&game_status));
+            if let Err(e) = self.with_round(|round| round.pass_cards(&player_name, cards)) {
+                error!("{} failed to pass cards: {}", player_name, e);
+            }
+        }
+    }
+
+    fn play_dummy_card(&mut self, timed_out: bool) -> bool {
+        let player_name = match self.round.as_ref().and_then(|round| round.turn().cloned()) {
+            Some(player_name) => player_name,
+            None => return false,
+        };
+        let is_dummy = self.seats.iter().any(|seat| {
+            seat.player_name == player_name && seat.dummy.is_some()
+        });
+        if !is_dummy && !timed_out {
+            return false;
+        }
+        if !is_dummy {
+            info!("{} ran out of time, playing for them", player_name);
+        }
+
+        let game_status = self.game_status(&player_name);
+        let chosen = self.stand_in(&player_name, |strategy| {
+            strategy.play_card(&game_status)
+        });
+        let card = if self.with_round(|round| round.valid_plays(&player_name))
+            .contains(&chosen)
+        {
+            chosen
+        } else {
+            debug!("{} chose invalid card {}", player_name, chosen);
+            self.with_round(|round| round.valid_plays(&player_name))
+                .into_iter()
+                .next()
+                .expect("No valid cards to play!")
+        };
+        self.with_round(|round| round.play_card(&player_name, card))
+            .map(|_| true)
+            .unwrap_or_else(|e| {
+                error!("{} failed to play card: {}", player_name, e);
+                false
+            })
+    }
+
+    fn stand_in<A, F>(&mut self, player_name: &PlayerName, f: F) -> A
+    where
+        F: FnOnce(&mut CardStrategy) -> A,
+    {
+        match self.seats
+            .iter_mut()
+            .find(|seat| &seat.player_name == player_name)
+            .and_then(|seat| seat.dummy.as_mut()) {
+            Some(dummy) => f(dummy),
+            None => f(&mut SimpleCardStrategy::new(player_name.clone())),
+        }
+    }
+
+    fn with_round<A, F>(&mut self, f: F) -> A
+    where
+        F: FnOnce(&mut Round) -> A,
+    {
+        f(self.round.as_mut().expect("No round in progress"))
+    }
+
+    fn running_round(&mut self, game_state: HeartsGameInstanceState) -> Result<&mut Round> {
+        if self.game_state != GameInstanceState::Running {
+            return Err(Error::game(Some("Game is not running")));
+        }
+        match self.round {
+            Some(ref mut round) if round.game_state() == game_state => Ok(round),
+            _ => Err(Error::game(Some(format!("Round is not {:?}", game_state)))),
+        }
+    }
+
+    fn seated(&self, player_name: &PlayerName) -> bool {
+        self.seats.iter().any(|seat| &seat.player_name == player_name)
+    }
+
+    fn participants(&self) -> Vec<GameParticipant> {
+        self.seats
+            .iter()
+            .enumerate()
+            .map(|(index, seat)| {
+                GameParticipant {
+                    team_name: seat.player_name.clone(),
+                    left_participant: self.seats[(index + 1) % self.seats.len()]
+                        .player_name
+                        .clone(),
+                    number_of_cards_in_hand: 0,
+                    has_turn: false,
+                    current_score: 0,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::Duration;
+    use std::time::Instant;
+
+    fn started_table(player_name: &PlayerName) -> (Table, Instant) {
+        let now = Instant::now();
+        let mut table = Table::new(Duration::from_secs(0));
+        table.join(player_name, now).unwrap();
+        table.tick(now);
+        (table, now)
+    }
+
+    #[test]
+    fn fills_empty_seats_with_dummies() {
+        let player_name = PlayerName::new("Derek Williams");
+        let (table, _) = started_table(&player_name);
+        let game_status = table.game_status(&player_name);
+
+        assert_eq!(GameInstanceState::Running, game_status.current_game_state);
+        assert_eq!(4, game_status.game_players.len());
+        assert_eq!(13, game_status.my_current_hand.len());
+        assert_eq!(
+            PlayerName::new("Dummy-0"),
+            game_status.game_players[0].left_participant
+        );
+    }
+
+    #[test]
+    fn rejects_cards_out_of_turn() {
+        let player_name = PlayerName::new("Derek Williams");
+        let (mut table, start) = started_table(&player_name);
+        let now = start + Duration::from_secs(5);
+        table.tick(now);
+        let hand = table.game_status(&player_name).my_current_hand;
+        table.pass_cards(&player_name, hand.iter().take(3).collect()).unwrap();
+        for _ in 0..engine::PLAYER_COUNT {
+            if !table.game_status(&player_name).is_my_turn {
+                table.tick(now);
+            }
+        }
+        assert!(table.game_status(&player_name).is_my_turn);
+
+        // A card the dummy could play on its own turn, so only the turn is wrong.
+        let dummy = PlayerName::new("Dummy-0");
+        let dummy_card = table.round.as_ref().unwrap().valid_plays(&dummy).iter().next().unwrap();
+        assert!(table.play_card(&dummy, dummy_card, now).is_err());
+
+        let card = table.game_status(&player_name).legal_plays().iter().next().unwrap();
+        assert!(table.play_card(&player_name, card, now).is_ok());
+    }
+
+    #[test]
+    fn plays_for_absent_players() {
+        let player_name = PlayerName::new("Derek Williams");
+        let (mut table, start) = started_table(&player_name);
+
+        let mut now = start;
+        while table.game_status(&player_name).current_game_state == GameInstanceState::Running {
+            now = now + Duration::from_secs(20);
+            table.tick(now);
+        }
+
+        let game_status = table.game_status(&player_name);
+        assert_eq!(GameInstanceState::Finished, game_status.current_game_state);
+        assert_eq!(engine::ROUND_COUNT, game_status.current_round_id);
+        assert_eq!(13, game_status.game_deals.len());
+        assert_eq!(3, game_status.cards_passed_by_me.len());
+    }
+}
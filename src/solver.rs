@@ -0,0 +1,615 @@
+use card::Card;
+use card::CardSet;
+use card::Rank;
+use card::Suit;
+use deal::Deal;
+use error::Error;
+use error::Result;
+use game_status::PlayerName;
+use game_status::RoundParameters;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::i32;
+
+const SEATS: usize = 4;
+
+/// Solves the rest of a round with every hand known. A player's value is the lowest score
+/// they can be sure of when the other three play against them, counting the points already
+/// taken this round and a moon shot the way the engine scores it.
+///
+/// The search is exact but exponential, so it is meant for the last few deals of a round.
+pub struct Solver {
+    seat_order: Vec<PlayerName>,
+    cards: Vec<Card>,
+    card_points: Vec<i32>,
+    moon_points: i32,
+    suits: Vec<u64>,
+    hearts: u64,
+    pointless: u64,
+    two_of_clubs: u64,
+    start: State,
+    table: HashMap<Key, Bounds>,
+}
+
+/// Cards are bits laid out as in `CardSet`, so each suit is a contiguous run.
+#[derive(Clone, Copy)]
+struct State {
+    hands: [u64; SEATS],
+    trick: [usize; SEATS],
+    trick_len: usize,
+    turn: usize,
+    points: [i32; SEATS],
+    takers: u8,
+    first_deal: bool,
+    hearts_broken: bool,
+}
+
+/// Player, hands, player to lead, the player's points and who has taken points so far.
+type Key = (usize, [u64; SEATS], usize, i32, u8);
+
+#[derive(Clone, Copy)]
+struct Bounds {
+    lower: i32,
+    upper: i32,
+}
+
+impl Solver {
+    /// Players are given in playing order, each player's left participant being the next
+    /// one in the list.
+    pub fn new(
+        seat_order: &[PlayerName],
+        hands: &BTreeMap<PlayerName, CardSet>,
+        game_deals: &[Deal],
+        in_progress_deal: Option<&Deal>,
+        round_parameters: &RoundParameters,
+    ) -> Result<Solver> {
+        if seat_order.len() != SEATS {
+            return Err(Error::game(Some(format!("Hearts needs {} players", SEATS))));
+        }
+        let cards = Card::all().into_iter().collect::<Vec<_>>();
+        let card_points = cards
+            .iter()
+            .map(|card| round_parameters.points(card))
+            .collect::<Vec<_>>();
+        let suit_mask = |suit: Suit| Card::all().suit(suit).bits();
+        let mut solver = Solver {
+            seat_order: seat_order.to_vec(),
+            moon_points: card_points.iter().filter(|&&points| points > 0).sum(),
+            suits: cards.iter().map(|card| suit_mask(card.suit)).collect(),
+            hearts: suit_mask(Suit::Heart),
+            pointless: card_points
+                .iter()
+                .enumerate()
+                .filter(|&(_, &points)| points <= 0)
+                .fold(0, |mask, (index, _)| mask | 1 << index),
+            two_of_clubs: 0,
+            cards: cards,
+            card_points: card_points,
+            start: State {
+                hands: [0; SEATS],
+                trick: [0; SEATS],
+                trick_len: 0,
+                turn: 0,
+                points: [0; SEATS],
+                takers: 0,
+                first_deal: game_deals.iter().all(|deal| deal.deal_cards.is_empty()),
+                hearts_broken: false,
+            },
+            table: HashMap::new(),
+        };
+        solver.two_of_clubs = solver.bit(&Rank::Two.of(Suit::Club));
+
+        let mut seen = 0;
+        for (seat, player_name) in seat_order.iter().enumerate() {
+            let hand = hands.get(player_name).cloned().unwrap_or_default();
+            if let Some(card) = hand.intersection(CardSet::from_bits(seen)).iter().next() {
+                return Err(Error::game(Some(format!("{} is dealt twice", card))));
+            }
+            seen |= hand.bits();
+            solver.start.hands[seat] = hand.bits();
+        }
+
+        let mut leader = None;
+        for deal in game_deals.iter().filter(|deal| !deal.deal_cards.is_empty()) {
+            let winner = deal.deal_winner
+                .as_ref()
+                .ok_or_else(|| Error::game(Some("Deal has no winner")))
+                .and_then(|winner| solver.seat(winner))?;
+            for deal_card in &deal.deal_cards {
+                let bit = solver.bit(&deal_card.card);
+                if seen & bit != 0 {
+                    return Err(Error::game(Some(format!("{} is played twice", deal_card.card))));
+                }
+                seen |= bit;
+                let points = solver.points(bit);
+                solver.start.points[winner] += points;
+                if points > 0 {
+                    solver.start.takers |= 1 << winner;
+                }
+                solver.start.hearts_broken |= bit & solver.hearts != 0;
+            }
+            leader = Some(winner);
+        }
+
+        let deal_cards = in_progress_deal
+            .map(|deal| deal.deal_cards.as_slice())
+            .unwrap_or_default();
+        if deal_cards.len() >= SEATS {
+            return Err(Error::game(Some("Deal in progress is already complete")));
+        }
+        if let Some(deal_card) = deal_cards.first() {
+            leader = Some(solver.seat(&deal_card.player_name)?);
+        }
+        let leader = match leader {
+            Some(leader) => leader,
+            None => {
+                let two_of_clubs = solver.two_of_clubs;
+                solver.start.hands.iter().position(|&hand| hand & two_of_clubs != 0).unwrap_or(0)
+            }
+        };
+        for (position, deal_card) in deal_cards.iter().enumerate() {
+            if solver.seat(&deal_card.player_name)? != (leader + position) % SEATS {
+                return Err(Error::game(Some("Deal in progress is out of turn")));
+            }
+            solver.start.trick[position] = solver.bit(&deal_card.card).trailing_zeros() as usize;
+        }
+        solver.start.trick_len = deal_cards.len();
+        solver.start.turn = (leader + deal_cards.len()) % SEATS;
+        Ok(solver)
+    }
+
+    /// The player to play next, if the round is not over yet.
+    #[allow(dead_code)]
+    pub fn turn(&self) -> Option<&PlayerName> {
+        if self.start.hands[self.start.turn] == 0 {
+            None
+        } else {
+            Some(&self.seat_order[self.start.turn])
+        }
+    }
+
+    /// The lowest score the player can be sure of for the round.
+    #[allow(dead_code)]
+    pub fn min_points(&mut self, player_name: &PlayerName) -> Result<i32> {
+        let player = self.seat(player_name)?;
+        let start = self.start;
+        Ok(self.value(start, player, i32::MIN, i32::MAX))
+    }
+
+    /// The lowest score each player can be sure of for the round.
+    #[allow(dead_code)]
+    pub fn solve(&mut self) -> BTreeMap<PlayerName, i32> {
+        let start = self.start;
+        (0..SEATS)
+            .map(|player| {
+                let value = self.value(start, player, i32::MIN, i32::MAX);
+                (self.seat_order[player].clone(), value)
+            })
+            .collect()
+    }
+
+    /// For every card the player to play may play, the lowest score they can then be sure of.
+    pub fn card_values(&mut self) -> BTreeMap<Card, i32> {
+        let start = self.start;
+        let mut values = BTreeMap::new();
+        let mut legal = self.legal(&start);
+        while legal != 0 {
+            let index = legal.trailing_zeros() as usize;
+            legal &= legal - 1;
+            let state = self.apply(&start, index);
+            let value = self.value(state, start.turn, i32::MIN, i32::MAX);
+            values.insert(self.cards[index], value);
+        }
+        values
+    }
+
+    fn value(&mut self, state: State, player: usize, mut alpha: i32, mut beta: i32) -> i32 {
+        if state.trick_len == 0 && state.hands.iter().all(|&hand| hand == 0) {
+            return self.final_score(&state, player);
+        }
+
+        let key = if state.trick_len == 0 {
+            Some((player, state.hands, state.turn, state.points[player], state.takers))
+        } else {
+            None
+        };
+        let mut bounds = Bounds {
+            lower: i32::MIN,
+            upper: i32::MAX,
+        };
+        if let Some(stored) = key.and_then(|key| self.table.get(&key).cloned()) {
+            if stored.lower >= beta || stored.lower == stored.upper {
+                return stored.lower;
+            }
+            if stored.upper <= alpha {
+                return stored.upper;
+            }
+            alpha = alpha.max(stored.lower);
+            beta = beta.min(stored.upper);
+            bounds = stored;
+        }
+
+        let (window_alpha, window_beta) = (alpha, beta);
+        let minimizing = state.turn == player;
+        let mut best = if minimizing { i32::MAX } else { i32::MIN };
+        for index in self.moves(&state) {
+            let next = self.apply(&state, index);
+            let value = self.value(next, player, alpha, beta);
+            if minimizing {
+                best = best.min(value);
+                beta = beta.min(best);
+            } else {
+                best = best.max(value);
+                alpha = alpha.max(best);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        if let Some(key) = key {
+            if best <= window_alpha {
+                bounds.upper = best;
+            } else if best >= window_beta {
+                bounds.lower = best;
+            } else {
+                bounds = Bounds {
+                    lower: best,
+                    upper: best,
+                };
+            }
+            self.table.insert(key, bounds);
+        }
+        best
+    }
+
+    /// Legal cards, keeping only one of the cards that are next to each other in a suit,
+    /// counting the cards still in play, and are worth the same.
+    fn moves(&self, state: &State) -> Vec<usize> {
+        let in_play = state.hands.iter().fold(0, |mask, hand| mask | hand) |
+            state.trick[..state.trick_len]
+                .iter()
+                .fold(0, |mask, &index| mask | 1 << index);
+        let legal = self.legal(state);
+        let mut moves = Vec::new();
+        let mut remaining = legal;
+        while remaining != 0 {
+            let index = remaining.trailing_zeros() as usize;
+            remaining &= remaining - 1;
+            let below = in_play & self.suits[index] & ((1 << index) - 1);
+            if below != 0 {
+                let previous = 63 - below.leading_zeros() as usize;
+                if legal & 1 << previous != 0 &&
+                    self.card_points[previous] == self.card_points[index]
+                {
+                    continue;
+                }
+            }
+            moves.push(index);
+        }
+        moves
+    }
+
+    /// The same rules as `rules::legal_plays`, on bits.
+    fn legal(&self, state: &State) -> u64 {
+        let hand = state.hands[state.turn];
+        let candidates = if state.trick_len > 0 {
+            let following = hand & self.suits[state.trick[0]];
+            if following == 0 { hand } else { following }
+        } else if state.first_deal && hand & self.two_of_clubs != 0 {
+            return self.two_of_clubs;
+        } else if state.hearts_broken {
+            hand
+        } else {
+            prefer(hand, !self.hearts)
+        };
+        if state.first_deal {
+            prefer(candidates, self.pointless)
+        } else {
+            candidates
+        }
+    }
+
+    fn apply(&self, state: &State, index: usize) -> State {
+        let mut next = *state;
+        next.hands[state.turn] &= !(1 << index);
+        next.trick[state.trick_len] = index;
+        next.trick_len += 1;
+        if next.trick_len < SEATS {
+            next.turn = (state.turn + 1) % SEATS;
+            return next;
+        }
+
+        let leader = (state.turn + 1) % SEATS;
+        let lead_suit = self.suits[next.trick[0]];
+        let (position, _) = next.trick
+            .iter()
+            .enumerate()
+            .filter(|&(_, &index)| self.suits[index] == lead_suit)
+            .max_by_key(|&(_, &index)| index)
+            .unwrap();
+        let winner = (leader + position) % SEATS;
+        for &index in &next.trick {
+            let points = self.card_points[index];
+            next.points[winner] += points;
+            if points > 0 {
+                next.takers |= 1 << winner;
+            }
+            next.hearts_broken |= (1 << index) & self.hearts != 0;
+        }
+        next.trick_len = 0;
+        next.first_deal = false;
+        next.turn = winner;
+        next
+    }
+
+    fn final_score(&self, state: &State, player: usize) -> i32 {
+        let points = state.points[player];
+        if state.takers.count_ones() != 1 {
+            points
+        } else if state.takers == 1 << player {
+            points - self.moon_points
+        } else {
+            points + self.moon_points
+        }
+    }
+
+    fn bit(&self, card: &Card) -> u64 {
+        CardSet::bit(card)
+    }
+
+    fn points(&self, bit: u64) -> i32 {
+        self.card_points[bit.trailing_zeros() as usize]
+    }
+
+    fn seat(&self, player_name: &PlayerName) -> Result<usize> {
+        self.seat_order
+            .iter()
+            .position(|other| other == player_name)
+            .ok_or_else(|| {
+                Error::game(Some(format!("{} is not playing in this round", player_name)))
+            })
+    }
+}
+
+/// Narrows the candidates to the preferred ones, unless there are none.
+fn prefer(candidates: u64, preferred: u64) -> u64 {
+    if candidates & preferred != 0 {
+        candidates & preferred
+    } else {
+        candidates
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use card::Rank::*;
+    use card::Suit::*;
+    use deal::DealCard;
+    use engine;
+    use rules;
+
+    use rand::Rng;
+    use rand::SeedableRng;
+    use rand::StdRng;
+
+    use std::collections::BTreeSet;
+
+    fn players() -> Vec<PlayerName> {
+        ["North", "East", "South", "West"]
+            .iter()
+            .map(|name| PlayerName::new(*name))
+            .collect()
+    }
+
+    fn hands(cards: Vec<Vec<Card>>) -> BTreeMap<PlayerName, CardSet> {
+        players()
+            .into_iter()
+            .zip(cards.into_iter().map(|hand| hand.into_iter().collect()))
+            .collect()
+    }
+
+    fn deal(deal_number: u32, leader: usize, cards: &[Card], winner: Option<usize>) -> Deal {
+        let players = players();
+        Deal {
+            deal_number: deal_number,
+            initiator: Some(players[leader].clone()),
+            suit: cards.first().map(|card| card.suit),
+            deal_cards: cards
+                .iter()
+                .enumerate()
+                .map(|(position, card)| {
+                    DealCard {
+                        player_name: players[(leader + position) % SEATS].clone(),
+                        card: *card,
+                    }
+                })
+                .collect(),
+            deal_winner: winner.map(|winner| players[winner].clone()),
+        }
+    }
+
+    /// Every card not in the hands, played in earlier deals all taken by the same player.
+    fn history(hands: &BTreeMap<PlayerName, CardSet>, winner: usize) -> Vec<Deal> {
+        let held = hands.values().flat_map(|hand| hand.iter()).collect();
+        Card::all()
+            .difference(held)
+            .iter()
+            .collect::<Vec<_>>()
+            .chunks(SEATS)
+            .enumerate()
+            .map(|(number, cards)| deal(number as u32 + 1, 0, cards, Some(winner)))
+            .collect()
+    }
+
+    /// Deals everything but the last few cards of each hand into earlier deals, taken by
+    /// random players.
+    fn random_position(
+        seed: usize,
+        hand_size: usize,
+    ) -> (BTreeMap<PlayerName, CardSet>, Vec<Deal>) {
+        let mut rng = StdRng::from_seed(&[seed]);
+        let mut deck = Card::all().into_iter().collect::<Vec<_>>();
+        rng.shuffle(&mut deck);
+        let hands = hands(
+            deck.chunks(hand_size)
+                .take(SEATS)
+                .map(|cards| cards.to_vec())
+                .collect(),
+        );
+        let game_deals = deck[hand_size * SEATS..]
+            .chunks(SEATS)
+            .enumerate()
+            .map(|(number, cards)| {
+                deal(number as u32 + 1, 0, cards, Some(rng.gen_range(0, SEATS)))
+            })
+            .collect();
+        (hands, game_deals)
+    }
+
+    /// Plain minimax over the shared rules, with no pruning or shortcuts.
+    fn minimax(
+        hands: &BTreeMap<PlayerName, CardSet>,
+        game_deals: &[Deal],
+        in_progress: &[Card],
+        leader: usize,
+        player: usize,
+        parameters: &RoundParameters,
+    ) -> i32 {
+        let players = players();
+        if in_progress.is_empty() && hands.values().all(|hand| hand.is_empty()) {
+            let mut points = vec![0; SEATS];
+            let mut takers = BTreeSet::new();
+            for deal in game_deals {
+                let winner = players
+                    .iter()
+                    .position(|other| Some(other) == deal.deal_winner.as_ref())
+                    .unwrap();
+                for deal_card in &deal.deal_cards {
+                    let card_points = parameters.points(&deal_card.card);
+                    points[winner] += card_points;
+                    if card_points > 0 {
+                        takers.insert(winner);
+                    }
+                }
+            }
+            let moon_points = parameters
+                .card_points
+                .values()
+                .filter(|&&points| points > 0)
+                .sum::<i32>();
+            return match (takers.len(), takers.contains(&player)) {
+                (1, true) => points[player] - moon_points,
+                (1, false) => points[player] + moon_points,
+                _ => points[player],
+            };
+        }
+
+        let turn = (leader + in_progress.len()) % SEATS;
+        let current = deal(game_deals.len() as u32 + 1, leader, in_progress, None);
+        let hand = hands[&players[turn]];
+        let values = rules::legal_plays(hand, game_deals, Some(&current), parameters)
+            .into_iter()
+            .map(|card| {
+                let mut hands = hands.clone();
+                hands.get_mut(&players[turn]).unwrap().remove(&card);
+                let mut played = in_progress.to_vec();
+                played.push(card);
+                if played.len() < SEATS {
+                    return minimax(&hands, game_deals, &played, leader, player, parameters);
+                }
+                let (position, _) = played
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, other)| other.suit == played[0].suit)
+                    .max_by_key(|&(_, other)| other.rank)
+                    .unwrap();
+                let winner = (leader + position) % SEATS;
+                let mut game_deals = game_deals.to_vec();
+                game_deals.push(deal(game_deals.len() as u32 + 1, leader, &played, Some(winner)));
+                minimax(&hands, &game_deals, &[], winner, player, parameters)
+            })
+            .collect::<Vec<_>>();
+        if turn == player {
+            values.into_iter().min().unwrap()
+        } else {
+            values.into_iter().max().unwrap()
+        }
+    }
+
+    #[test]
+    fn last_deal() {
+        let hands = hands(vec![
+            vec![Queen.of(Spade)],
+            vec![King.of(Spade)],
+            vec![Two.of(Heart)],
+            vec![Three.of(Diamond)],
+        ]);
+        let game_deals = history(&hands, 0);
+        let mut solver =
+            Solver::new(&players(), &hands, &game_deals, None, &engine::round_parameters(1))
+                .unwrap();
+
+        assert_eq!(Some(&PlayerName::new("North")), solver.turn());
+        let scores = solver.solve();
+        assert_eq!(12, scores[&PlayerName::new("North")]);
+        assert_eq!(14, scores[&PlayerName::new("East")]);
+        assert_eq!(0, scores[&PlayerName::new("South")]);
+    }
+
+    #[test]
+    fn shoots_the_moon() {
+        let hands = hands(vec![
+            vec![Two.of(Diamond), Three.of(Diamond), Four.of(Diamond)],
+            vec![Five.of(Diamond), Six.of(Diamond), Seven.of(Diamond)],
+            vec![Eight.of(Diamond), Nine.of(Diamond), Ten.of(Diamond)],
+            vec![Ace.of(Heart), Two.of(Spade), Three.of(Spade)],
+        ]);
+        let game_deals = history(&hands, 3);
+        let mut solver =
+            Solver::new(&players(), &hands, &game_deals, None, &engine::round_parameters(1))
+                .unwrap();
+
+        assert_eq!(Some(&PlayerName::new("West")), solver.turn());
+        assert_eq!(Some(&0), solver.card_values().get(&Ace.of(Heart)));
+        assert_eq!(0, solver.min_points(&PlayerName::new("West")).unwrap());
+        assert_eq!(26, solver.min_points(&PlayerName::new("North")).unwrap());
+    }
+
+    #[test]
+    fn agrees_with_plain_minimax() {
+        for seed in 0..6 {
+            let (hands, game_deals) = random_position(seed, 3);
+            let parameters = engine::round_parameters(seed as u32 % engine::ROUND_COUNT + 1);
+            let leader = players()
+                .iter()
+                .position(|player| game_deals.last().unwrap().deal_winner.as_ref() == Some(player))
+                .unwrap();
+            let mut solver =
+                Solver::new(&players(), &hands, &game_deals, None, &parameters).unwrap();
+            let scores = solver.solve();
+            for (player, player_name) in players().iter().enumerate() {
+                assert_eq!(
+                    minimax(&hands, &game_deals, &[], leader, player, &parameters),
+                    scores[player_name],
+                    "seed {}, {}",
+                    seed,
+                    player_name
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_inconsistent_hands() {
+        let hands = hands(vec![
+            vec![Queen.of(Spade)],
+            vec![Queen.of(Spade)],
+            vec![Two.of(Heart)],
+            vec![Three.of(Diamond)],
+        ]);
+        assert!(Solver::new(&players(), &hands, &[], None, &engine::round_parameters(1)).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use archive;
+use card::CardSet;
+use card::Rank;
+use card::Suit;
+use error::Result;
+use game_status::PlayerName;
+use game_status::RoundParameters;
+use replay::Replay;
+use replay::RoundReplay;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// How we have done in the games logged to a directory, by round number and over all rounds,
+/// and how each opponent scored. Only finished rounds count, but for passing.
+pub struct Stats {
+    games: usize,
+    skipped: usize,
+    rounds: BTreeMap<u32, RoundStats>,
+    /// Each opponent's score in every finished round played against them.
+    opponents: BTreeMap<PlayerName, Vec<i32>>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct RoundStats {
+    finished: usize,
+    /// Points in the cards we took, before any moon shot is accounted for.
+    points_taken: i32,
+    queen_captures: usize,
+    our_moons: usize,
+    moons_against: usize,
+    /// Rounds in which hearts were played, and the sum of the deals they were first played in.
+    hearts_broken: usize,
+    break_deals: usize,
+    passes: usize,
+    points_passed: i32,
+    points_received: i32,
+}
+
+impl Stats {
+    /// Games that cannot be read, or in which it was never our turn, are skipped.
+    pub fn collect<P: AsRef<Path>>(log_dir: P) -> Result<Stats> {
+        let log_dir = log_dir.as_ref();
+        let mut stats = Stats {
+            games: 0,
+            skipped: 0,
+            rounds: BTreeMap::new(),
+            opponents: BTreeMap::new(),
+        };
+        for game_id in archive::game_ids(log_dir)? {
+            match Replay::load(log_dir, &game_id) {
+                Ok(replay) => stats.add_game(&replay),
+                Err(e) => {
+                    warn!("Skipping {}: {}", game_id, e);
+                    stats.skipped += 1;
+                }
+            }
+        }
+        Ok(stats)
+    }
+
+    fn add_game(&mut self, replay: &Replay) {
+        let me = match replay.me() {
+            Some(me) => me,
+            None => {
+                self.skipped += 1;
+                return;
+            }
+        };
+        self.games += 1;
+        for round in replay.rounds() {
+            self.rounds
+                .entry(round.round_id)
+                .or_insert_with(RoundStats::default)
+                .add_round(round, me);
+            if round.finished {
+                for player_name in round.players.iter().filter(|player_name| *player_name != me) {
+                    self.opponents
+                        .entry(player_name.clone())
+                        .or_insert_with(Vec::new)
+                        .push(round.score(player_name));
+                }
+            }
+        }
+    }
+}
+
+impl RoundStats {
+    fn add_round(&mut self, round: &RoundReplay, me: &PlayerName) {
+        if !round.passed.is_empty() {
+            self.passes += 1;
+            self.points_passed += points(round.passed, &round.parameters);
+            self.points_received += points(round.received, &round.parameters);
+        }
+        if !round.finished {
+            return;
+        }
+        self.finished += 1;
+        self.points_taken += round.points(me);
+
+        let queen = Rank::Queen.of(Suit::Spade);
+        let queen_taken = round.deals.iter().any(|deal| {
+            deal.deal_winner.as_ref() == Some(me) &&
+                deal.deal_cards.iter().any(|deal_card| deal_card.card == queen)
+        });
+        if queen_taken {
+            self.queen_captures += 1;
+        }
+
+        match round.shooter() {
+            Some(shooter) if shooter == me => self.our_moons += 1,
+            Some(_) => self.moons_against += 1,
+            None => (),
+        }
+
+        let broken = round.deals.iter().position(|deal| {
+            deal.deal_cards.iter().any(|deal_card| deal_card.card.suit == Suit::Heart)
+        });
+        if let Some(index) = broken {
+            self.hearts_broken += 1;
+            self.break_deals += index + 1;
+        }
+    }
+
+    fn add(&mut self, other: &RoundStats) {
+        self.finished += other.finished;
+        self.points_taken += other.points_taken;
+        self.queen_captures += other.queen_captures;
+        self.our_moons += other.our_moons;
+        self.moons_against += other.moons_against;
+        self.hearts_broken += other.hearts_broken;
+        self.break_deals += other.break_deals;
+        self.passes += other.passes;
+        self.points_passed += other.points_passed;
+        self.points_received += other.points_received;
+    }
+
+    fn write<W: fmt::Write>(&self, out: &mut W, round: &str) -> fmt::Result {
+        writeln!(
+            out,
+            "{: <5} {: >6} {: >6} {: >9} {: >7} {: >7} {: >7} {: >6} {: >10} {: >12}",
+            round,
+            self.finished,
+            self.points_taken,
+            average(self.points_taken as f64, self.finished),
+            self.queen_captures,
+            self.our_moons,
+            self.moons_against,
+            average(self.break_deals as f64, self.hearts_broken),
+            average(self.points_passed as f64, self.passes),
+            average(self.points_received as f64, self.passes)
+        )
+    }
+}
+
+fn points(cards: CardSet, parameters: &RoundParameters) -> i32 {
+    cards.iter().map(|card| parameters.points(&card)).sum()
+}
+
+fn average(total: f64, count: usize) -> String {
+    if count == 0 {
+        "-".to_owned()
+    } else {
+        format!("{:.2}", total / count as f64)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{} games, {} skipped", self.games, self.skipped)?;
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{: <5} {: >6} {: >6} {: >9} {: >7} {: >7} {: >7} {: >6} {: >10} {: >12}",
+            "Round",
+            "Rounds",
+            "Taken",
+            "Per round",
+            "Q♠",
+            "Moons",
+            "Against",
+            "Hearts",
+            "Pts passed",
+            "Pts received"
+        )?;
+        let mut all = RoundStats::default();
+        for (round_id, round) in &self.rounds {
+            round.write(f, &round_id.to_string())?;
+            all.add(round);
+        }
+        all.write(f, "All")?;
+        writeln!(
+            f,
+            "Taken is the points in the cards we took, before moon shots are accounted for, Q♠ \
+             the rounds we took it in,\nHearts the average deal they were first played in, and \
+             Pts passed and Pts received the average points\nin the cards passed each way."
+        )?;
+
+        let width = self.opponents
+            .keys()
+            .map(|player_name| format!("{}", player_name).chars().count())
+            .max()
+            .unwrap_or_default()
+            .max(8);
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{: <w$} {: >6} {: >6} {: >4} {: >6} {: >4}",
+            "Opponent",
+            "Rounds",
+            "Mean",
+            "Min",
+            "Median",
+            "Max",
+            w = width
+        )?;
+        for (player_name, scores) in &self.opponents {
+            let mut scores = scores.clone();
+            scores.sort();
+            writeln!(
+                f,
+                "{: <w$} {: >6} {: >6.2} {: >4} {: >6} {: >4}",
+                format!("{}", player_name),
+                scores.len(),
+                scores.iter().sum::<i32>() as f64 / scores.len() as f64,
+                scores[0],
+                scores[scores.len() / 2],
+                scores[scores.len() - 1],
+                w = width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine;
+    use engine::Round;
+    use game_status::GameInstanceState;
+
+    /// Plays a test round, returning it as replayed for the first player.
+    fn replayed_round() -> (Round, RoundReplay, PlayerName) {
+        let mut round = engine::test_round(engine::round_parameters(1), 7);
+        engine::play_test_round(&mut round, |_, _| ());
+        let me = engine::test_players().remove(0);
+        let status = round.game_status("stats", GameInstanceState::Finished, &me);
+        let replayed = RoundReplay::new(1, &status, Some(&me));
+        (round, replayed, me)
+    }
+
+    #[test]
+    fn counts_finished_rounds() {
+        let (round, replayed, me) = replayed_round();
+        let mut stats = RoundStats::default();
+        stats.add_round(&replayed, &me);
+
+        assert_eq!(1, stats.finished);
+        assert_eq!(round.points(&me), stats.points_taken);
+        assert_eq!(1, stats.passes);
+        assert_eq!(points(replayed.passed, &replayed.parameters), stats.points_passed);
+        assert_eq!(1, stats.hearts_broken);
+        assert_eq!(
+            replayed.shooter().is_some(),
+            stats.our_moons + stats.moons_against == 1
+        );
+
+        let mut all = RoundStats::default();
+        all.add(&stats);
+        all.add(&stats);
+        assert_eq!(2 * stats.points_taken, all.points_taken);
+        assert_eq!(2, all.finished);
+    }
+
+    #[test]
+    fn shows_opponent_scores() {
+        let mut opponents = BTreeMap::new();
+        opponents.insert(PlayerName::new("Bill"), vec![26, 0, 3]);
+        let stats = Stats {
+            games: 1,
+            skipped: 0,
+            rounds: BTreeMap::new(),
+            opponents: opponents,
+        };
+        let output = format!("{}", stats);
+
+        assert!(output.contains("1 games, 0 skipped"));
+        assert!(output.contains("Bill          3   9.67    0      3   26"));
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+
+/// What a strategy weighed in a decision, for the decision log or to explain a play. Cards are
+/// written as they are displayed, as in "♠Q".
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct Decision {
+    /// The cards considered to play, best first. Passes and plays of the only legal card
+    /// score none, and are logged without them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<Candidate>,
+    pub shooting_the_moon: bool,
+    /// The player thought to be shooting the moon, whom the strategy then plays against.
+    pub possible_shooter: Option<String>,
+    /// Whether the scores were turned around to take points rather than avoid them, because
+    /// someone is shooting the moon.
+    pub inverted: bool,
+}
+
+/// The score of a card to play, its points as they were before scaling. Candidates are
+/// ranked on the points in order, then on the rank, lowest first.
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub card: String,
+    pub definite_points: f32,
+    pub potential_points: f32,
+    pub later_potential_points: f32,
+    pub rank: i32,
+    /// Why the card ranks where it does, against the card ranked just above it.
+    pub reason: String,
+}
+
+impl Candidate {
+    pub fn new(
+        card: String,
+        definite_points: f32,
+        potential_points: f32,
+        later_potential_points: f32,
+        rank: i32,
+    ) -> Candidate {
+        Candidate {
+            card: card,
+            definite_points: definite_points,
+            potential_points: potential_points,
+            later_potential_points: later_potential_points,
+            rank: rank,
+            reason: String::new(),
+        }
+    }
+
+    /// The first component the card scores worse on than a better card.
+    fn worse_on(&self, better: &Candidate) -> &'static str {
+        if self.definite_points != better.definite_points {
+            "definite points"
+        } else if self.potential_points != better.potential_points {
+            "potential points"
+        } else if self.later_potential_points != better.later_potential_points {
+            "later potential points"
+        } else {
+            "rank"
+        }
+    }
+}
+
+impl Decision {
+    /// Gives each candidate, ranked best first, its reason.
+    pub fn with_reasons(mut self) -> Decision {
+        for index in 0..self.candidates.len() {
+            let reason = match index {
+                0 if self.candidates.len() == 1 => "the only legal card".to_owned(),
+                0 => format!("best on {}", self.candidates[1].worse_on(&self.candidates[0])),
+                _ => {
+                    let better = &self.candidates[index - 1];
+                    let worse_on = self.candidates[index].worse_on(better);
+                    format!("behind {} on {}", better.card, worse_on)
+                }
+            };
+            self.candidates[index].reason = reason;
+        }
+        self
+    }
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.shooting_the_moon {
+            writeln!(f, "Shooting the moon, taking points")?;
+        } else if let Some(ref shooter) = self.possible_shooter {
+            writeln!(f, "{} may be shooting the moon, taking points", shooter)?;
+        }
+        writeln!(
+            f,
+            "{: <4} {: >8} {: >9} {: >7} {: >4}  Reason",
+            "Card",
+            "Definite",
+            "Potential",
+            "Later",
+            "Rank"
+        )?;
+        for candidate in &self.candidates {
+            writeln!(
+                f,
+                "{: <4} {: >8.3} {: >9.3} {: >7.3} {: >4}  {}",
+                candidate.card,
+                candidate.definite_points,
+                candidate.potential_points,
+                candidate.later_potential_points,
+                candidate.rank,
+                candidate.reason
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json;
+
+    #[test]
+    fn explains_ranking() {
+        let decision = Decision {
+            candidates: vec![
+                Candidate::new("♣9".to_owned(), 0.0, 0.0, 0.0, -9),
+                Candidate::new("♣8".to_owned(), 0.0, 0.0, 0.0, -8),
+                Candidate::new("♥2".to_owned(), 0.0, 1.0, 0.0, -2),
+            ],
+            ..Decision::default()
+        }.with_reasons();
+
+        let reasons = decision
+            .candidates
+            .iter()
+            .map(|candidate| candidate.reason.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec!["best on rank", "behind ♣9 on rank", "behind ♣8 on potential points"],
+            reasons
+        );
+        assert!(format!("{}", decision).contains("♥2      0.000     1.000   0.000   -2"));
+    }
+
+    #[test]
+    fn leaves_out_unscored_candidates() {
+        let passed = Decision {
+            shooting_the_moon: true,
+            ..Decision::default()
+        };
+        let json = serde_json::to_value(&passed).unwrap();
+        assert!(json.get("candidates").is_none());
+        assert_eq!(Some(true), json["shooting_the_moon"].as_bool());
+    }
+}
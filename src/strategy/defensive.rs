@@ -1,6 +1,9 @@
+use strategy::Candidate;
 use strategy::CardStrategy;
+use strategy::Decision;
 
 use card::Card;
+use card::CardSet;
 use card::Suit;
 use card::Rank;
 use deal::Deal;
@@ -8,8 +11,13 @@ use game_status::GameStatus;
 use game_status::RoundParameters;
 use game_status::GameParticipant;
 use game_status::PlayerName;
+use opponents::Opponents;
+use opponents::Profile;
+
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::de;
 
-use std::fmt;
 use std::iter;
 use std::collections::BTreeMap;
 use std::collections::BTreeSet;
@@ -17,14 +25,77 @@ use std::collections::BTreeSet;
 #[derive(Debug)]
 pub struct DefensiveCardStrategy {
     player_name: PlayerName,
+    params: DefensiveParams,
     shooting_the_moon: bool,
+    last_decision: Option<Decision>,
+}
+
+/// The weights the defensive strategy plays by. The defaults are the ones it was tuned with.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct DefensiveParams {
+    /// We try to shoot the moon when passing if the cards in hand sure to win a deal, times
+    /// this, outnumber the cards in hand.
+    pub pass_shooting_multiplier: f32,
+    /// As `pass_shooting_multiplier`, when choosing a card to play.
+    pub play_shooting_multiplier: f32,
+    /// Points are scaled by this and rounded before cards are compared on them.
+    #[serde(deserialize_with = "positive")]
+    pub points_scale: f32,
+    /// Added to the points of hearts when choosing cards to pass, lower passing sooner.
+    pub heart_pass_bonus: i32,
+    /// Added to the points of the queen, king and ace of spades when choosing cards to pass.
+    pub high_spade_pass_bonus: i32,
+    /// How often a player tries to shoot the moon, per round, when they are no more likely to
+    /// be shooting than anyone.
+    pub typical_moon_rate: f32,
+    /// A player is taken for a shooter on this many fewer points won for each attempt per
+    /// round they make more than typical, or more points for each they make fewer.
+    pub moon_rate_weight: f32,
+    /// How often a player following suit plays under a card already played anyway.
+    pub typical_duck_rate: f32,
+    /// How much of the chance that the players left to play in a deal take it from us goes,
+    /// for each duck they make more often than typical.
+    pub duck_rate_weight: f32,
+}
+
+impl Default for DefensiveParams {
+    fn default() -> DefensiveParams {
+        DefensiveParams {
+            pass_shooting_multiplier: 2.5,
+            play_shooting_multiplier: 2.0,
+            points_scale: 1000.0,
+            heart_pass_bonus: -2,
+            high_spade_pass_bonus: -2,
+            typical_moon_rate: 0.05,
+            moon_rate_weight: 20.0,
+            typical_duck_rate: 0.6,
+            duck_rate_weight: 2.0,
+        }
+    }
+}
+
+/// Reads a number that has to be above 0, as points are divided by it when explained.
+fn positive<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let value = f32::deserialize(deserializer)?;
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(de::Error::custom(format!("expected a number above 0, found {}", value)))
+    }
 }
 
 impl DefensiveCardStrategy {
     pub fn new(player_name: PlayerName) -> DefensiveCardStrategy {
+        Self::with_params(player_name, DefensiveParams::default())
+    }
+
+    pub fn with_params(player_name: PlayerName, params: DefensiveParams) -> DefensiveCardStrategy {
         DefensiveCardStrategy {
             player_name: player_name,
+            params: params,
             shooting_the_moon: false,
+            last_decision: None,
         }
     }
 
@@ -37,8 +108,8 @@ impl DefensiveCardStrategy {
 
         let deal_void_suits = Self::deal_void_suits(&void_suits, &plays_left);
 
-        let safe_remaining_cards_iter = game_status.unplayed_cards().into_iter().filter(|other| {
-            !deal_void_suits.contains(&other.suit)
+        let safe_remaining_cards_all = deal_void_suits.iter().fold(remaining_cards, |cards, suit| {
+            cards.difference(cards.suit(*suit))
         });
 
         let safe_remaining_cards =
@@ -46,27 +117,26 @@ impl DefensiveCardStrategy {
                 deal.deal_cards.is_empty()
             })
             {
-                safe_remaining_cards_iter.collect::<BTreeSet<_>>()
+                safe_remaining_cards_all
             } else {
-                safe_remaining_cards_iter
-                    .filter(|other| !game_status.cards_passed_by_me.contains(other))
-                    .collect()
+                safe_remaining_cards_all.difference(game_status.cards_passed_by_me)
             };
 
         let potential_points = self.potential_points(
             card,
             &game_status.game_players,
             &game_status.in_progress_deal,
-            &safe_remaining_cards,
+            safe_remaining_cards,
             &void_suits,
             &game_status.round_parameters,
+            &game_status.opponents,
         );
 
         let definite_points = if Self::will_win_deal(
             card,
             &game_status.game_players,
             &game_status.in_progress_deal,
-            &safe_remaining_cards,
+            safe_remaining_cards,
         )
         {
             potential_points
@@ -75,7 +145,7 @@ impl DefensiveCardStrategy {
         };
 
         let later_potential_points = 0.0 -
-            Self::later_potential_points(card, &remaining_cards, &game_status.round_parameters);
+            Self::later_potential_points(card, remaining_cards, &game_status.round_parameters);
 
         let rank_modifier = if game_status.round_parameters.points(card) < 0 {
             -1
@@ -85,19 +155,22 @@ impl DefensiveCardStrategy {
 
         let card_rank = 0 - (u32::from(card.rank) as i32) * rank_modifier;
 
+        let scale = self.params.points_scale;
         CardScore {
-            definite_points: (definite_points * 1000.0) as i32,
-            potential_points: (potential_points * 1000.0) as i32,
-            later_potential_points: (later_potential_points * 1000.0) as i32,
+            definite_points: (definite_points * scale) as i32,
+            potential_points: (potential_points * scale) as i32,
+            later_potential_points: (later_potential_points * scale) as i32,
             rank: card_rank,
         }
     }
 
     fn possible_shooter<'a>(
+        &self,
         game_players: &'a [GameParticipant],
         in_progress_deal: &Option<Deal>,
         deals: &[Deal],
         round_parameters: &RoundParameters,
+        opponents: &Opponents,
     ) -> Option<&'a GameParticipant> {
         let possible_shooters = Self::possible_shooters(game_players, deals, round_parameters);
 
@@ -106,7 +179,9 @@ impl DefensiveCardStrategy {
         if possible_shooters.len() == 1 {
             possible_shooters
                 .into_iter()
-                .filter(|&(_, shoot_score)| shoot_score > shoot_target)
+                .filter(|&(shooter, shoot_score)| {
+                    shoot_score > shoot_target - self.moon_allowance(&shooter.team_name, opponents)
+                })
                 .map(|(player, _)| player)
                 .filter(|shooter| {
                     in_progress_deal.as_ref().map_or(true, |deal| {
@@ -119,6 +194,34 @@ impl DefensiveCardStrategy {
         }
     }
 
+    /// How many fewer points than usual a player needs to have won to be taken for a shooter,
+    /// from how often they have tried to shoot the moon before.
+    fn moon_allowance(&self, player_name: &PlayerName, opponents: &Opponents) -> i32 {
+        opponents
+            .profile(player_name)
+            .and_then(Profile::moon_rate)
+            .map_or(0, |rate| {
+                ((rate - self.params.typical_moon_rate) * self.params.moon_rate_weight).round() as
+                    i32
+            })
+    }
+
+    /// How much of the chance that the players left to play in a deal take it goes, from how
+    /// much more often than typical they have ducked before.
+    fn ducking(&self, plays_left: &BTreeSet<&PlayerName>, opponents: &Opponents) -> f32 {
+        if plays_left.is_empty() {
+            return 0.0;
+        }
+        let excess = plays_left
+            .iter()
+            .filter_map(|player_name| {
+                opponents.profile(player_name).and_then(Profile::duck_rate)
+            })
+            .map(|rate| (rate - self.params.typical_duck_rate).max(0.0))
+            .sum::<f32>();
+        (excess / plays_left.len() as f32 * self.params.duck_rate_weight).min(1.0)
+    }
+
     fn player_might_win_deal(player: &PlayerName, in_progress_deal: &Deal) -> bool {
         in_progress_deal
             .suit
@@ -186,7 +289,7 @@ impl DefensiveCardStrategy {
                         card,
                         &game_status.game_players,
                         &game_status.in_progress_deal,
-                        &remaining_cards,
+                        remaining_cards,
                     )
                 })
                       .collect::<Vec<_>>()
@@ -280,16 +383,16 @@ impl DefensiveCardStrategy {
         card: &Card,
         game_players: &[GameParticipant],
         in_progress_deal: &Option<Deal>,
-        remaining_cards: &BTreeSet<Card>,
+        remaining_cards: CardSet,
     ) -> bool {
         Self::can_win_deal(card, in_progress_deal) &&
             (Self::plays_left(game_players, in_progress_deal).is_empty() ||
                  {
                      let suit = Self::deal_suit(in_progress_deal).unwrap_or(&card.suit);
                      remaining_cards
+                         .suit(*suit)
                          .iter()
-                         .filter(|other| &other.suit == suit)
-                         .max()
+                         .next_back()
                          .map_or(true, |winning_card| card.rank > winning_card.rank)
                  })
     }
@@ -319,16 +422,12 @@ impl DefensiveCardStrategy {
         players
     }
 
-    fn dealt_cards(in_progress_deal: &Option<Deal>) -> BTreeSet<&Card> {
+    fn dealt_cards(in_progress_deal: &Option<Deal>) -> CardSet {
         in_progress_deal
-            .as_ref()
-            .map(|deal| {
-                deal.deal_cards
-                    .iter()
-                    .map(|deal_card| &deal_card.card)
-                    .collect()
-            })
-            .unwrap_or_default()
+            .iter()
+            .flat_map(|deal| deal.deal_cards.iter())
+            .map(|deal_card| deal_card.card)
+            .collect()
     }
 
     fn potential_points(
@@ -336,9 +435,10 @@ impl DefensiveCardStrategy {
         card: &Card,
         game_players: &[GameParticipant],
         in_progress_deal: &Option<Deal>,
-        remaining_cards: &BTreeSet<Card>,
+        remaining_cards: CardSet,
         void_suits: &BTreeMap<&PlayerName, BTreeSet<Suit>>,
         round_parameters: &RoundParameters,
+        opponents: &Opponents,
     ) -> f32 {
         if Self::can_win_deal(card, in_progress_deal) {
             let dealt_cards = Self::dealt_cards(in_progress_deal);
@@ -347,29 +447,25 @@ impl DefensiveCardStrategy {
 
             let dealt_points = dealt_cards
                 .iter()
-                .map(|other| round_parameters.points(other))
+                .map(|other| round_parameters.points(&other))
                 .sum::<i32>() as f32;
 
             let suit_points = remaining_cards
+                .suit(card.suit)
                 .iter()
-                .chain(iter::once(card))
-                .filter(|other| other.suit == card.suit)
+                .chain(iter::once(*card))
                 .filter(|other| other.rank <= card.rank)
-                .map(|other| round_parameters.points(other))
+                .map(|other| round_parameters.points(&other))
                 .sum::<i32>() as f32;
 
             let other_points = remaining_cards
+                .difference(remaining_cards.suit(card.suit))
                 .iter()
-                .filter(|other| other.suit != card.suit)
-                .map(|other| round_parameters.points(other))
+                .map(|other| round_parameters.points(&other))
                 .filter(|points| points > &0)
                 .sum::<i32>() as f32;
 
-            let number_of_suit = remaining_cards
-                .iter()
-                .filter(|other| other.suit == card.suit)
-                .map(|_| 1)
-                .sum::<i32>();
+            let number_of_suit = remaining_cards.suit(card.suit).len() as i32;
             let number_dealt = dealt_cards.len();
 
             let safe_target = 9.0 + card_points + dealt_points - (number_dealt as f32);
@@ -381,15 +477,22 @@ impl DefensiveCardStrategy {
                     1.0
                 };
 
+            let plays_left = Self::plays_left(game_players, in_progress_deal);
+
             let suit_win_points = if number_dealt < 3 {
-                Self::chance_of_win(card, game_players, in_progress_deal, remaining_cards) *
-                    suit_points * suit_win_modifier
+                let ducking = self.ducking(&plays_left, opponents);
+                let chance_of_win = Self::chance_of_win(
+                    card,
+                    game_players,
+                    in_progress_deal,
+                    remaining_cards,
+                    ducking,
+                );
+                chance_of_win * suit_points * suit_win_modifier
             } else {
                 0.0
             };
 
-            let plays_left = Self::plays_left(game_players, in_progress_deal);
-
             let voider = void_suits
                 .iter()
                 .filter(|&(player_name, ref player_void_suits)| {
@@ -401,12 +504,8 @@ impl DefensiveCardStrategy {
             let other_win_points = if self.shooting_the_moon || voider ||
                 ((number_of_suit as f32) < safe_target && number_dealt < 3)
             {
-                let other_cards = remaining_cards
-                    .iter()
-                    .chain(dealt_cards)
-                    .cloned()
-                    .collect::<BTreeSet<_>>();
-                Self::chance_of_later_win(card, &other_cards) * other_points
+                let other_cards = remaining_cards.union(dealt_cards);
+                Self::chance_of_later_win(card, other_cards) * other_points
             } else {
                 0.0
             };
@@ -419,22 +518,22 @@ impl DefensiveCardStrategy {
 
     fn later_potential_points(
         card: &Card,
-        remaining_cards: &BTreeSet<Card>,
+        remaining_cards: CardSet,
         round_parameters: &RoundParameters,
     ) -> f32 {
         let card_points = round_parameters.points(card) as f32;
 
         let suit_points = remaining_cards
+            .suit(card.suit)
             .iter()
-            .filter(|other| other.suit == card.suit)
             .filter(|other| other.rank < card.rank)
-            .map(|other| round_parameters.points(other))
+            .map(|other| round_parameters.points(&other))
             .sum::<i32>() as f32;
 
         let other_points = remaining_cards
+            .difference(remaining_cards.suit(card.suit))
             .iter()
-            .filter(|other| other.suit != card.suit)
-            .map(|other| round_parameters.points(other))
+            .map(|other| round_parameters.points(&other))
             .filter(|points| points > &0)
             .sum::<i32>() as f32;
 
@@ -443,16 +542,12 @@ impl DefensiveCardStrategy {
         card_points + suit_points + other_win_points
     }
 
-    fn chance_of_later_win(card: &Card, remaining_cards: &BTreeSet<Card>) -> f32 {
-        let suit_cards = remaining_cards
-            .iter()
-            .filter(|other| other.suit == card.suit)
-            .collect::<Vec<_>>();
+    fn chance_of_later_win(card: &Card, remaining_cards: CardSet) -> f32 {
+        let suit_cards = remaining_cards.suit(card.suit);
         let will_win_count = suit_cards
             .iter()
             .filter(|other| other.rank < card.rank)
-            .collect::<Vec<_>>()
-            .len();
+            .count();
         if suit_cards.is_empty() {
             1.0
         } else {
@@ -464,7 +559,8 @@ impl DefensiveCardStrategy {
         card: &Card,
         game_players: &[GameParticipant],
         in_progress_deal: &Option<Deal>,
-        remaining_cards: &BTreeSet<Card>,
+        remaining_cards: CardSet,
+        ducking: f32,
     ) -> f32 {
         if Self::will_win_deal(card, game_players, in_progress_deal, remaining_cards) {
             1.0
@@ -473,49 +569,97 @@ impl DefensiveCardStrategy {
                 !Self::plays_left(game_players, in_progress_deal).is_empty()
             {
                 let suit_cards = remaining_cards
-                    .iter()
-                    .chain(
-                        in_progress_deal
-                            .iter()
-                            .flat_map(|deal| deal.deal_cards.iter())
-                            .map(|deal_card| &deal_card.card),
-                    )
-                    .filter(|other| other.suit == card.suit)
-                    .collect::<Vec<_>>();
+                    .union(Self::dealt_cards(in_progress_deal))
+                    .suit(card.suit);
                 let will_win_count = suit_cards
                     .iter()
                     .filter(|other| other.rank < card.rank)
-                    .collect::<Vec<_>>()
-                    .len();
-                if suit_cards.is_empty() {
+                    .count();
+                let chance = if suit_cards.is_empty() {
                     1.0
                 } else {
                     (will_win_count as f32) / (suit_cards.len() as f32)
-                }
+                };
+                chance + (1.0 - chance) * ducking
             } else {
                 0.0
             }
         }
     }
 
-    fn pass_card<'a>(
-        hand: &'a BTreeSet<Card>,
-        remaining_cards: &BTreeSet<Card>,
+    /// Scores every legal card, best first, turning the scores around to take points when
+    /// someone is shooting the moon.
+    fn evaluate(&self, game_status: &GameStatus) -> Evaluation {
+        let initial_scores = game_status
+            .legal_plays()
+            .into_iter()
+            .map(|card| (self.score_card(&card, game_status), card))
+            .collect::<BTreeSet<_>>();
+
+        let possible_shooter = self.possible_shooter(
+            &game_status.game_players,
+            &game_status.in_progress_deal,
+            &game_status.game_deals,
+            &game_status.round_parameters,
+            &game_status.opponents,
+        ).map(|shooter| shooter.team_name.clone());
+
+        let i_am_shooter = self.am_i_shooter(game_status, self.params.play_shooting_multiplier);
+        let inverted = i_am_shooter || possible_shooter.is_some();
+
+        let scores = if inverted {
+            initial_scores
+                .into_iter()
+                .map(|(card_score, card)| (card_score.invert(), card))
+                .collect()
+        } else {
+            initial_scores
+        };
+        Evaluation {
+            scores: scores,
+            i_am_shooter: i_am_shooter,
+            possible_shooter: possible_shooter,
+            inverted: inverted,
+        }
+    }
+
+    fn decision(&self, evaluation: &Evaluation) -> Decision {
+        Decision {
+            candidates: evaluation
+                .scores
+                .iter()
+                .map(|&(ref score, card)| score.candidate(card, self.params.points_scale))
+                .collect(),
+            shooting_the_moon: evaluation.i_am_shooter,
+            possible_shooter: evaluation
+                .possible_shooter
+                .as_ref()
+                .map(|shooter| format!("{}", shooter)),
+            inverted: evaluation.inverted,
+        }.with_reasons()
+    }
+
+    fn pass_card(
+        &self,
+        hand: CardSet,
+        remaining_cards: CardSet,
         round_parameters: &RoundParameters,
         shooting: bool,
-    ) -> Option<&'a Card> {
-        hand.iter()
-            .filter(|card| !remaining_cards.contains(card))
+    ) -> Option<Card> {
+        hand.difference(remaining_cards)
+            .iter()
             .map(|card| {
                 let mut points = 0 -
-                    (Self::later_potential_points(card, &remaining_cards, round_parameters) *
-                         1000.0) as i32;
+                    (Self::later_potential_points(&card, remaining_cards, round_parameters) *
+                         self.params.points_scale) as i32;
                 let mut rank = u32::from(card.rank) as i32;
                 if card.suit == Suit::Heart {
-                    points = points - 2 // higher priority on hearts
+                    // higher priority on hearts
+                    points = points + self.params.heart_pass_bonus
                 }
                 if card.suit == Suit::Spade && card.rank > Rank::Jack {
-                    points = points - 2 // higher priority on queen of spades (and friends)
+                    // higher priority on queen of spades (and friends)
+                    points = points + self.params.high_spade_pass_bonus
                 }
                 if shooting {
                     points = points.abs()
@@ -537,114 +681,58 @@ impl CardStrategy for DefensiveCardStrategy {
         &self.player_name
     }
 
-    fn pass_cards<'a>(&mut self, game_status: &'a GameStatus) -> Vec<&'a Card> {
+    fn take_decision(&mut self) -> Option<Decision> {
+        self.last_decision.take()
+    }
+
+    fn pass_cards(&mut self, game_status: &GameStatus) -> Vec<Card> {
         self.shooting_the_moon = false;
 
-        info!(
-            "My Hand : {}",
-            game_status
-                .my_current_hand
-                .iter()
-                .map(|card| format!("{}", card))
-                .collect::<Vec<String>>()
-                .join(" ")
-        );
+        info!("My Hand : {}", game_status.my_current_hand);
         let mut remaining_cards = game_status.unplayed_cards();
 
-        let i_am_shooter = self.am_i_shooter(game_status, 2.5);
+        let i_am_shooter = self.am_i_shooter(game_status, self.params.pass_shooting_multiplier);
         self.shooting_the_moon = i_am_shooter;
 
-        let card1 = Self::pass_card(
-            &game_status.my_initial_hand,
-            &remaining_cards,
-            &game_status.round_parameters,
-            i_am_shooter,
-        );
-        if let Some(card) = card1 {
-            remaining_cards.insert(card.clone());
-        };
-        let card2 = Self::pass_card(
-            &game_status.my_initial_hand,
-            &remaining_cards,
-            &game_status.round_parameters,
-            i_am_shooter,
-        );
-        if let Some(card) = card2 {
-            remaining_cards.insert(card.clone());
-        };
-        let card3 = Self::pass_card(
-            &game_status.my_initial_hand,
-            &remaining_cards,
-            &game_status.round_parameters,
-            i_am_shooter,
-        );
-
-        vec![card1, card2, card3]
-            .into_iter()
-            .filter_map(|card| card)
-            .collect()
-    }
-
-    fn play_card<'a>(&mut self, game_status: &'a GameStatus) -> &'a Card {
-        let two_of_clubs = Rank::Two.of(Suit::Club);
-        if let Some(card) = game_status.my_current_hand.iter().find(|&card| {
-            card == &two_of_clubs
-        })
-        {
-            card
-        } else {
-            let current_suit = game_status.in_progress_deal.as_ref().and_then(
-                |deal| deal.suit,
-            );
-            let mut valid_cards = game_status
-                .my_current_hand
-                .iter()
-                .filter(|card| Some(card.suit) == current_suit)
-                .collect::<Vec<&Card>>();
-
-            if valid_cards.is_empty() {
-                valid_cards.extend(&game_status.my_current_hand);
-            }
-
-            let initial_evaluation = valid_cards
-                .into_iter()
-                .map(|card| (self.score_card(card, game_status), card))
-                .collect::<BTreeSet<_>>();
-
-            let possible_shooter = Self::possible_shooter(
-                &game_status.game_players,
-                &game_status.in_progress_deal,
-                &game_status.game_deals,
+        let mut cards = Vec::new();
+        for _ in 0..game_status.round_parameters.cards_to_pass() {
+            let card = self.pass_card(
+                game_status.my_initial_hand,
+                remaining_cards,
                 &game_status.round_parameters,
+                i_am_shooter,
             );
+            if let Some(card) = card {
+                remaining_cards.insert(card);
+                cards.push(card);
+            }
+        }
+        self.last_decision = Some(Decision {
+            shooting_the_moon: i_am_shooter,
+            ..Decision::default()
+        });
+        cards
+    }
 
-            let i_am_shooter = self.am_i_shooter(game_status, 2.0);
-            self.shooting_the_moon = i_am_shooter;
+    fn play_card(&mut self, game_status: &GameStatus) -> Card {
+        let valid_cards = game_status.legal_plays();
+        if valid_cards.len() == 1 {
+            self.last_decision = Some(Decision {
+                shooting_the_moon: self.shooting_the_moon,
+                ..Decision::default()
+            });
+            valid_cards.into_iter().next().unwrap()
+        } else {
+            let evaluation = self.evaluate(game_status);
+            self.shooting_the_moon = evaluation.i_am_shooter;
 
-            if i_am_shooter {
+            if evaluation.i_am_shooter {
                 info!("Shooting the moon!");
-            } else if let Some(shooter) = possible_shooter {
-                info!("Possible shooter detected: {}", shooter.team_name);
+            } else if let Some(ref shooter) = evaluation.possible_shooter {
+                info!("Possible shooter detected: {}", shooter);
             }
 
-            let evaluation = if i_am_shooter || possible_shooter.is_some() {
-                initial_evaluation
-                    .into_iter()
-                    .map(|(card_score, card)| (card_score.invert(), card))
-                    .collect()
-            } else {
-                initial_evaluation
-            };
-
-            info!(
-                "Unplayed: {}",
-                game_status
-                    .unplayed_cards()
-                    .iter()
-                    .map(|card| format!("{}", card))
-                    .collect::<Vec<_>>()
-                    .join(" ")
-            );
+            info!("Unplayed: {}", game_status.unplayed_cards());
             info!(
                 "Void: {}",
                 Self::void_suits(game_status)
@@ -663,25 +751,34 @@ impl CardStrategy for DefensiveCardStrategy {
                     .collect::<Vec<_>>()
                     .join(" ")
             );
-            info!(
-                "My Hand:  {}",
-                game_status
-                    .my_current_hand
-                    .iter()
-                    .map(|card| format!("{}", card))
-                    .collect::<Vec<String>>()
-                    .join(" ")
-            );
-            for item in &evaluation {
+            info!("My Hand:  {}", game_status.my_current_hand);
+            for item in &evaluation.scores {
                 let &(ref score, ref card) = item;
-                info!("{}: {}", card, score);
+                info!("{}: {}", card, score.describe(self.params.points_scale));
             }
+            self.last_decision = Some(self.decision(&evaluation));
 
-            evaluation.iter().map(|&(_, card)| card).next().expect(
+            evaluation.scores.iter().map(|&(_, card)| card).next().expect(
                 "No valid cards to play!",
             )
         }
     }
+
+    fn explain(&self, game_status: &GameStatus) -> Option<Decision> {
+        if game_status.legal_plays().is_empty() {
+            None
+        } else {
+            Some(self.decision(&self.evaluate(game_status)))
+        }
+    }
+}
+
+/// The legal cards with their scores, best first.
+struct Evaluation {
+    scores: BTreeSet<(CardScore, Card)>,
+    i_am_shooter: bool,
+    possible_shooter: Option<PlayerName>,
+    inverted: bool,
 }
 
 #[derive(Debug, PartialEq, PartialOrd, Eq, Ord)]
@@ -701,16 +798,25 @@ impl CardScore {
             rank: self.rank,
         }
     }
-}
 
-impl fmt::Display for CardScore {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(
-            f,
+    /// The score of the card as it is logged.
+    fn candidate(&self, card: Card, scale: f32) -> Candidate {
+        Candidate::new(
+            format!("{}", card),
+            self.definite_points as f32 / scale,
+            self.potential_points as f32 / scale,
+            self.later_potential_points as f32 / scale,
+            self.rank,
+        )
+    }
+
+    /// The points as they were before scaling, and the rank.
+    fn describe(&self, scale: f32) -> String {
+        format!(
             "{: >7.3}, {: >7.3}, {: >7.3}, {: >3.2}",
-            self.definite_points as f32 / 1000.0,
-            self.potential_points as f32 / 1000.0,
-            self.later_potential_points as f32 / 1000.0,
+            self.definite_points as f32 / scale,
+            self.potential_points as f32 / scale,
+            self.later_potential_points as f32 / scale,
             self.rank
         )
     }
@@ -726,6 +832,7 @@ mod tests {
     use game_status::GameStatus;
     use game_status::dto::GameStatusDto;
     use strategy::CardStrategy;
+    use strategy::registry;
 
     use std::convert::TryFrom;
     use error::Error;
@@ -752,9 +859,7 @@ mod tests {
     fn should_play(name: &str, expected_card: Card) {
         let player_name = PlayerName::new("Derek Williams");
         let game_status = open_scenario(name);
-        let card = DefensiveCardStrategy::new(player_name)
-            .play_card(&game_status)
-            .clone();
+        let card = DefensiveCardStrategy::new(player_name).play_card(&game_status);
         assert_eq!(expected_card, card);
     }
 
@@ -820,4 +925,59 @@ mod tests {
         normal_game_1_02_13 => Two.of(Spade)
     }
 
+    #[test]
+    fn records_ranked_candidates() {
+        let game_status = open_scenario("should_prevent_shooter_1");
+        let mut strategy = DefensiveCardStrategy::new(PlayerName::new("Derek Williams"));
+        let card = strategy.play_card(&game_status);
+        let decision = strategy.take_decision().unwrap();
+
+        assert_eq!(game_status.legal_plays().len(), decision.candidates.len());
+        assert_eq!(format!("{}", card), decision.candidates[0].card);
+        assert!(decision.possible_shooter.is_some());
+        assert!(decision.inverted);
+        assert_eq!(None, strategy.take_decision());
+        assert_eq!(Some(decision), strategy.explain(&game_status));
+    }
+
+    #[test]
+    fn suspects_players_who_often_shoot_the_moon() {
+        let mut game_status = open_scenario("should_play_low_club_2");
+        let strategy = DefensiveCardStrategy::new(PlayerName::new("Derek Williams"));
+        assert_eq!(None, strategy.explain(&game_status).unwrap().possible_shooter);
+
+        game_status.opponents = serde_json::from_str(
+            "{\"players\": {\"Dummy-2\": {\"rounds\": 10, \"moon_attempts\": 10}}}",
+        ).unwrap();
+        let decision = strategy.explain(&game_status).unwrap();
+        assert_eq!(Some("Dummy-2".to_owned()), decision.possible_shooter);
+        assert!(decision.inverted);
+    }
+
+    #[test]
+    fn counts_on_players_who_often_duck() {
+        let strategy = DefensiveCardStrategy::new(PlayerName::new("Derek Williams"));
+        let (ducker, other) = (PlayerName::new("Ducker"), PlayerName::new("Other"));
+        let opponents = serde_json::from_str(
+            "{\"players\": {\"Ducker\": {\"follows\": 40, \"ducks\": 36}}}",
+        ).unwrap();
+        let plays_left = vec![&ducker, &other].into_iter().collect();
+
+        assert_eq!(0.0, strategy.ducking(&plays_left, &Opponents::default()));
+        assert!((strategy.ducking(&plays_left, &opponents) - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn sample_params_are_the_defaults() {
+        let file_names = ["samples/strategies/defensive.json", "samples/strategies/defensive.toml"];
+        for file_name in &file_names {
+            let parameters = registry::read_parameters(file_name).unwrap();
+            let params: DefensiveParams = registry::parameters(&parameters).unwrap();
+            assert_eq!(DefensiveParams::default(), params);
+        }
+        let unknown = serde_json::from_str("{\"points\": 1}").unwrap();
+        assert!(registry::parameters::<DefensiveParams>(&unknown).is_err());
+        let unscaled = serde_json::from_str("{\"points_scale\": 0}").unwrap();
+        assert!(registry::parameters::<DefensiveParams>(&unscaled).is_err());
+    }
 }
@@ -1,5 +1,8 @@
 mod simple;
 mod defensive;
+mod monte_carlo;
+mod registry;
+mod decision;
 
 use card::Card;
 use game_status::GameStatus;
@@ -9,9 +12,51 @@ use std::fmt::Debug;
 
 pub use strategy::simple::SimpleCardStrategy;
 pub use strategy::defensive::DefensiveCardStrategy;
+pub use strategy::defensive::DefensiveParams;
+pub use strategy::monte_carlo::MonteCarloCardStrategy;
+pub use strategy::registry::Registry;
+pub use strategy::decision::Candidate;
+pub use strategy::decision::Decision;
+pub use strategy::registry::parameters;
+pub use strategy::registry::read_parameters;
 
-pub trait CardStrategy: Debug {
-    fn pass_cards<'a>(&mut self, game_status: &'a GameStatus) -> Vec<&'a Card>;
-    fn play_card<'a>(&mut self, game_status: &'a GameStatus) -> &'a Card;
+/// Strategies are sent to a worker thread to decide, so the client can stop waiting for them.
+pub trait CardStrategy: Debug + Send {
+    fn pass_cards(&mut self, game_status: &GameStatus) -> Vec<Card>;
+    fn play_card(&mut self, game_status: &GameStatus) -> Card;
     fn player_name(&self) -> &PlayerName;
+
+    /// What was weighed in the last pass or play, taken so it is reported only once. Only
+    /// strategies that can say why they chose a card report anything.
+    fn take_decision(&mut self) -> Option<Decision> {
+        None
+    }
+
+    /// Ranks the cards that could be played, best first, with the reasons for their ranking,
+    /// without playing any. Only strategies that can say why they choose a card explain.
+    fn explain(&self, _game_status: &GameStatus) -> Option<Decision> {
+        None
+    }
+}
+
+impl<A: CardStrategy + ?Sized> CardStrategy for Box<A> {
+    fn pass_cards(&mut self, game_status: &GameStatus) -> Vec<Card> {
+        (**self).pass_cards(game_status)
+    }
+
+    fn play_card(&mut self, game_status: &GameStatus) -> Card {
+        (**self).play_card(game_status)
+    }
+
+    fn player_name(&self) -> &PlayerName {
+        (**self).player_name()
+    }
+
+    fn take_decision(&mut self) -> Option<Decision> {
+        (**self).take_decision()
+    }
+
+    fn explain(&self, game_status: &GameStatus) -> Option<Decision> {
+        (**self).explain(game_status)
+    }
 }
@@ -0,0 +1,507 @@
+use strategy::CardStrategy;
+use strategy::DefensiveCardStrategy;
+
+use card::Card;
+use card::CardSet;
+use deal::Deal;
+use deal::DealCard;
+use game_status::GameStatus;
+use game_status::PlayerName;
+use game_status::RoundParameters;
+use inference::Inference;
+use rules;
+use solver::Solver;
+
+use rand::Rng;
+use rand::SeedableRng;
+use rand::StdRng;
+
+use std::collections::BTreeMap;
+
+const SAMPLES: usize = 40;
+const DEAL_ATTEMPTS: usize = 50;
+const ENDGAME_HAND_SIZE: usize = 4;
+
+/// Deals the cards we cannot see to the other players many times over, consistent with
+/// everything we know about them, plays each deal out to the end of the round for every
+/// card we may play and picks the card with the lowest average score. Once only a few cards
+/// are left the deals are solved exactly instead. Passing is left to the defensive strategy.
+#[derive(Debug)]
+pub struct MonteCarloCardStrategy {
+    player_name: PlayerName,
+    passing: DefensiveCardStrategy,
+    samples: usize,
+    rng: StdRng,
+}
+
+/// How many deals to play out for each card, and the seed they are dealt from.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default, deny_unknown_fields)]
+pub struct MonteCarloParameters {
+    pub samples: usize,
+    pub seed: usize,
+}
+
+impl Default for MonteCarloParameters {
+    fn default() -> MonteCarloParameters {
+        MonteCarloParameters {
+            samples: SAMPLES,
+            seed: 0,
+        }
+    }
+}
+
+/// What we know about the hand of another player.
+#[derive(Debug)]
+struct Opponent {
+    player_name: PlayerName,
+    hand_size: usize,
+    known_cards: CardSet,
+    possible_cards: CardSet,
+}
+
+/// The rest of a round played with every hand in the open.
+struct Playout<'a> {
+    parameters: &'a RoundParameters,
+    seat_order: &'a [PlayerName],
+    hands: BTreeMap<PlayerName, CardSet>,
+    game_deals: Vec<Deal>,
+    in_progress_deal: Deal,
+}
+
+impl MonteCarloCardStrategy {
+    #[allow(dead_code)]
+    pub fn new(player_name: PlayerName) -> MonteCarloCardStrategy {
+        Self::with_parameters(player_name, MonteCarloParameters::default())
+    }
+
+    pub fn with_parameters(
+        player_name: PlayerName,
+        parameters: MonteCarloParameters,
+    ) -> MonteCarloCardStrategy {
+        MonteCarloCardStrategy {
+            player_name: player_name.clone(),
+            passing: DefensiveCardStrategy::new(player_name),
+            samples: parameters.samples.max(1),
+            rng: StdRng::from_seed(&[parameters.seed]),
+        }
+    }
+
+    /// Players in playing order, starting with us.
+    fn seat_order(&self, game_status: &GameStatus) -> Vec<PlayerName> {
+        let left_of = game_status
+            .game_players
+            .iter()
+            .map(|player| (&player.team_name, &player.left_participant))
+            .collect::<BTreeMap<_, _>>();
+        let mut seat_order = vec![self.player_name.clone()];
+        while let Some(&next) = left_of.get(seat_order.last().unwrap()) {
+            if next == &self.player_name || seat_order.len() == left_of.len() {
+                break;
+            }
+            seat_order.push(next.clone());
+        }
+        if seat_order.len() == left_of.len() {
+            seat_order
+        } else {
+            game_status
+                .game_players
+                .iter()
+                .map(|player| player.team_name.clone())
+                .collect()
+        }
+    }
+
+    fn opponents(&self, game_status: &GameStatus, seat_order: &[PlayerName]) -> Vec<Opponent> {
+        let inference = Inference::new(game_status, &self.player_name);
+        seat_order
+            .iter()
+            .filter(|&player_name| player_name != &self.player_name)
+            .map(|player_name| {
+                Opponent {
+                    player_name: player_name.clone(),
+                    hand_size: inference.hand_size(player_name),
+                    known_cards: inference.known_cards(player_name),
+                    possible_cards: inference.possible_cards(player_name),
+                }
+            })
+            .collect()
+    }
+
+    /// Deals the cards we cannot see to the opponents. Only cards an opponent could be
+    /// holding are dealt to them, unless no such deal turns up.
+    fn deal_hidden_cards(
+        &mut self,
+        game_status: &GameStatus,
+        opponents: &[Opponent],
+    ) -> BTreeMap<PlayerName, CardSet> {
+        let known_cards = opponents
+            .iter()
+            .fold(CardSet::new(), |cards, opponent| cards.union(opponent.known_cards));
+        let mut hidden_cards = game_status
+            .unplayed_cards()
+            .difference(known_cards)
+            .into_iter()
+            .collect::<Vec<_>>();
+
+        for &constrained in &[true, false] {
+            for _ in 0..DEAL_ATTEMPTS {
+                self.rng.shuffle(&mut hidden_cards);
+                if let Some(hands) =
+                    Self::try_deal(&mut self.rng, &hidden_cards, opponents, constrained)
+                {
+                    return hands;
+                }
+            }
+        }
+        unreachable!("Dealing without constraints cannot fail")
+    }
+
+    fn try_deal<R: Rng>(
+        rng: &mut R,
+        hidden_cards: &[Card],
+        opponents: &[Opponent],
+        constrained: bool,
+    ) -> Option<BTreeMap<PlayerName, CardSet>> {
+        let can_hold = |opponent: &Opponent, card: &Card| {
+            !constrained || opponent.possible_cards.contains(card)
+        };
+        let mut cards = hidden_cards.to_vec();
+        // The shuffle order is kept between equally constrained cards.
+        cards.sort_by_key(|card| {
+            opponents
+                .iter()
+                .filter(|opponent| can_hold(opponent, card))
+                .count()
+        });
+
+        let mut hands = opponents
+            .iter()
+            .map(|opponent| opponent.known_cards)
+            .collect::<Vec<_>>();
+        for card in cards {
+            let room = opponents
+                .iter()
+                .zip(&hands)
+                .map(|(opponent, hand)| if can_hold(opponent, &card) {
+                    opponent.hand_size.saturating_sub(hand.len())
+                } else {
+                    0
+                })
+                .collect::<Vec<_>>();
+            let total_room = room.iter().sum::<usize>();
+            if total_room == 0 {
+                if constrained {
+                    return None;
+                }
+                // More hidden cards than the opponents hold, nothing sensible to do.
+                continue;
+            }
+            let mut pick = rng.gen_range(0, total_room);
+            let index = room.iter()
+                .position(|&space| if pick < space {
+                    true
+                } else {
+                    pick -= space;
+                    false
+                })
+                .unwrap();
+            hands[index].insert(card);
+        }
+
+        Some(
+            opponents
+                .iter()
+                .map(|opponent| opponent.player_name.clone())
+                .zip(hands)
+                .collect(),
+        )
+    }
+}
+
+impl CardStrategy for MonteCarloCardStrategy {
+    fn player_name(&self) -> &PlayerName {
+        &self.player_name
+    }
+
+    fn pass_cards(&mut self, game_status: &GameStatus) -> Vec<Card> {
+        self.passing.pass_cards(game_status)
+    }
+
+    fn play_card(&mut self, game_status: &GameStatus) -> Card {
+        let valid_cards = game_status.legal_plays();
+        if valid_cards.len() == 1 {
+            return valid_cards.into_iter().next().unwrap();
+        }
+
+        let seat_order = self.seat_order(game_status);
+        let opponents = self.opponents(game_status, &seat_order);
+        let mut totals = valid_cards
+            .iter()
+            .map(|card| (card, 0))
+            .collect::<BTreeMap<_, _>>();
+        for _ in 0..self.samples {
+            let mut hands = self.deal_hidden_cards(game_status, &opponents);
+            hands.insert(self.player_name.clone(), game_status.my_current_hand);
+            let solved = if game_status.my_current_hand.len() <= ENDGAME_HAND_SIZE {
+                Solver::new(
+                    &seat_order,
+                    &hands,
+                    &game_status.game_deals,
+                    game_status.in_progress_deal.as_ref(),
+                    &game_status.round_parameters,
+                ).map(|mut solver| solver.card_values())
+                    .map_err(|e| warn!("Cannot solve sampled deal: {}", e))
+                    .ok()
+            } else {
+                None
+            };
+            for (card, total) in &mut totals {
+                *total += match solved {
+                    Some(ref values) => values[card],
+                    None => {
+                        let playout = Playout::new(game_status, &seat_order, hands.clone());
+                        playout.score(&self.player_name, card)
+                    }
+                };
+            }
+        }
+
+        for (card, total) in &totals {
+            debug!("{}: {: >7.3}", card, *total as f32 / self.samples as f32);
+        }
+        totals
+            .into_iter()
+            .min_by_key(|&(_, total)| total)
+            .map(|(card, _)| card)
+            .expect("No valid cards to play!")
+    }
+}
+
+impl<'a> Playout<'a> {
+    fn new(
+        game_status: &'a GameStatus,
+        seat_order: &'a [PlayerName],
+        hands: BTreeMap<PlayerName, CardSet>,
+    ) -> Playout<'a> {
+        let mut in_progress_deal = game_status.in_progress_deal.clone().unwrap_or_else(|| {
+            Deal {
+                deal_number: game_status.game_deals.len() as u32 + 1,
+                initiator: None,
+                suit: None,
+                deal_cards: Vec::new(),
+                deal_winner: None,
+            }
+        });
+        if in_progress_deal.suit.is_none() {
+            in_progress_deal.suit = in_progress_deal.deal_cards.first().map(
+                |deal_card| deal_card.card.suit,
+            );
+        }
+        Playout {
+            parameters: &game_status.round_parameters,
+            seat_order: seat_order,
+            hands: hands,
+            game_deals: game_status.game_deals.clone(),
+            in_progress_deal: in_progress_deal,
+        }
+    }
+
+    /// Our score for the round when we play the card and everyone then plays quickly.
+    fn score(mut self, player_name: &PlayerName, card: &Card) -> i32 {
+        let mut turn = self.play(player_name, *card);
+        while let Some(player_name) = turn {
+            let card = self.choose(&player_name);
+            turn = self.play(&player_name, card);
+        }
+        self.final_score(player_name)
+    }
+
+    /// Returns the player to play next, if any.
+    fn play(&mut self, player_name: &PlayerName, card: Card) -> Option<PlayerName> {
+        self.hands.get_mut(player_name).map(|hand| hand.remove(&card));
+        if self.in_progress_deal.deal_cards.is_empty() {
+            self.in_progress_deal.initiator = Some(player_name.clone());
+            self.in_progress_deal.suit = Some(card.suit);
+        }
+        self.in_progress_deal.deal_cards.push(DealCard {
+            player_name: player_name.clone(),
+            card: card,
+        });
+
+        if self.in_progress_deal.deal_cards.len() < self.seat_order.len() {
+            let index = self.seat_order
+                .iter()
+                .position(|other| other == player_name)
+                .unwrap();
+            return Some(self.seat_order[(index + 1) % self.seat_order.len()].clone());
+        }
+
+        let suit = self.in_progress_deal.suit;
+        let winner = self.in_progress_deal
+            .deal_cards
+            .iter()
+            .filter(|deal_card| Some(deal_card.card.suit) == suit)
+            .max_by_key(|deal_card| deal_card.card.rank)
+            .map(|deal_card| deal_card.player_name.clone())
+            .unwrap();
+        let deal_number = self.in_progress_deal.deal_number;
+        self.in_progress_deal.deal_winner = Some(winner.clone());
+        self.game_deals.push(self.in_progress_deal.clone());
+        self.in_progress_deal = Deal {
+            deal_number: deal_number + 1,
+            initiator: Some(winner.clone()),
+            suit: None,
+            deal_cards: Vec::new(),
+            deal_winner: None,
+        };
+        if self.hands.get(&winner).map_or(true, |hand| hand.is_empty()) {
+            None
+        } else {
+            Some(winner)
+        }
+    }
+
+    /// A quick policy: lead low, duck under the winning card when following, otherwise
+    /// win with the highest card only when last to play, and throw away points when void.
+    fn choose(&self, player_name: &PlayerName) -> Card {
+        let hand = self.hands[player_name];
+        let valid_cards = rules::legal_plays(
+            hand,
+            &self.game_deals,
+            Some(&self.in_progress_deal),
+            self.parameters,
+        );
+        let deal = &self.in_progress_deal;
+        let points = |card: &Card| self.parameters.points(card);
+
+        let card = match deal.suit {
+            None => {
+                valid_cards.into_iter().min_by_key(
+                    |card| (points(card) > 0, card.rank),
+                )
+            }
+            Some(suit) if valid_cards.iter().any(|card| card.suit == suit) => {
+                let winning_rank = deal.deal_cards
+                    .iter()
+                    .filter(|deal_card| deal_card.card.suit == suit)
+                    .map(|deal_card| deal_card.card.rank)
+                    .max();
+                let last = deal.deal_cards.len() + 1 == self.seat_order.len();
+                let ducking = valid_cards
+                    .iter()
+                    .filter(|card| Some(card.rank) < winning_rank)
+                    .max_by_key(|card| card.rank);
+                ducking.or_else(|| if last {
+                    valid_cards.into_iter().max_by_key(|card| card.rank)
+                } else {
+                    valid_cards.into_iter().min_by_key(|card| card.rank)
+                })
+            }
+            Some(_) => {
+                valid_cards.into_iter().max_by_key(
+                    |card| (points(card), card.rank),
+                )
+            }
+        };
+        card.expect("No valid cards to play!")
+    }
+
+    /// Points taken by the player, with a moon shot giving everyone else the points instead.
+    fn final_score(&self, player_name: &PlayerName) -> i32 {
+        let mut points = BTreeMap::new();
+        for deal in &self.game_deals {
+            let winner = deal.deal_winner.as_ref().unwrap();
+            for deal_card in &deal.deal_cards {
+                let card_points = self.parameters.points(&deal_card.card);
+                if card_points > 0 {
+                    *points.entry(winner).or_insert(0) += card_points;
+                }
+            }
+        }
+        let negative_points = self.game_deals
+            .iter()
+            .filter(|deal| deal.deal_winner.as_ref() == Some(player_name))
+            .flat_map(|deal| deal.deal_cards.iter())
+            .map(|deal_card| self.parameters.points(&deal_card.card))
+            .filter(|&card_points| card_points < 0)
+            .sum::<i32>();
+        let my_points = points.get(player_name).cloned().unwrap_or_default() + negative_points;
+
+        if points.len() == 1 {
+            let moon_points = points.values().sum::<i32>();
+            if points.contains_key(player_name) {
+                my_points - moon_points
+            } else {
+                my_points + moon_points
+            }
+        } else {
+            my_points
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use error::Error;
+    use game_status::dto::GameStatusDto;
+
+    use std::convert::TryFrom;
+    use std::fs::File;
+    use std::io::Read;
+
+    use serde_json;
+
+    fn open_scenario(name: &str) -> GameStatus {
+        let file_name = format!("samples/scenarios/{}.json", name);
+        let mut game_status_string = String::new();
+        File::open(file_name)
+            .unwrap()
+            .read_to_string(&mut game_status_string)
+            .unwrap();
+        let game_status_dto: GameStatusDto = serde_json::from_str(&game_status_string)
+            .map_err(Error::from)
+            .unwrap();
+        GameStatus::try_from(game_status_dto).unwrap()
+    }
+
+    fn strategy(game_status: &GameStatus) -> MonteCarloCardStrategy {
+        let player = game_status.game_players.iter().find(|player| player.has_turn);
+        MonteCarloCardStrategy::new(player.unwrap().team_name.clone())
+    }
+
+    #[test]
+    fn hidden_cards_are_dealt_consistently() {
+        let game_status = open_scenario("normal game 1 01 09");
+        let mut strategy = strategy(&game_status);
+        let seat_order = strategy.seat_order(&game_status);
+        let opponents = strategy.opponents(&game_status, &seat_order);
+        assert_eq!(3, opponents.len());
+
+        for _ in 0..20 {
+            let hands = strategy.deal_hidden_cards(&game_status, &opponents);
+            let dealt = hands.values().flat_map(|hand| hand.iter()).collect::<Vec<_>>();
+            assert_eq!(game_status.unplayed_cards().len(), dealt.len());
+            assert_eq!(
+                game_status.unplayed_cards(),
+                dealt.into_iter().collect::<CardSet>()
+            );
+            for opponent in &opponents {
+                let hand = &hands[&opponent.player_name];
+                assert_eq!(opponent.hand_size, hand.len());
+                assert!(opponent.known_cards.is_subset(*hand));
+                assert!(hand.is_subset(opponent.possible_cards));
+            }
+        }
+    }
+
+    #[test]
+    fn plays_valid_cards() {
+        for name in &["normal 1", "should play heart 1", "normal game 1 02 05"] {
+            let game_status = open_scenario(name);
+            let card = strategy(&game_status).play_card(&game_status);
+            assert!(game_status.legal_plays().contains(&card));
+        }
+    }
+}
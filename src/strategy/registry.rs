@@ -0,0 +1,160 @@
+use strategy::CardStrategy;
+use strategy::DefensiveCardStrategy;
+use strategy::MonteCarloCardStrategy;
+use strategy::SimpleCardStrategy;
+
+use error::Error;
+use error::Result;
+use game_status::PlayerName;
+
+use serde::de::DeserializeOwned;
+use serde_json;
+use serde_json::Value;
+use toml;
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Builds a strategy for a player from its parameters, which are `Value::Null` when none
+/// were given.
+pub type Factory = fn(PlayerName, &Value) -> Result<Box<CardStrategy>>;
+
+/// The strategies that can be chosen by name, from the command line or for a tournament.
+pub struct Registry {
+    entries: Vec<Entry>,
+}
+
+struct Entry {
+    name: &'static str,
+    description: &'static str,
+    factory: Factory,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry { entries: Vec::new() }
+    }
+
+    /// Every strategy in this crate.
+    pub fn standard() -> Registry {
+        let mut registry = Registry::new();
+        registry.register(
+            "defensive",
+            "Avoids taking points, and shoots the moon with a strong hand (parameters: see \
+             samples/strategies)",
+            |player_name, parameters| {
+                let params = self::parameters(parameters)?;
+                Ok(Box::new(DefensiveCardStrategy::with_params(player_name, params)))
+            },
+        );
+        registry.register(
+            "monte-carlo",
+            "Plays out sampled deals for each card (parameters: samples, seed)",
+            |player_name, parameters| {
+                let parameters = self::parameters(parameters)?;
+                Ok(Box::new(MonteCarloCardStrategy::with_parameters(player_name, parameters)))
+            },
+        );
+        registry.register(
+            "simple",
+            "Plays the first legal card",
+            |player_name, _| Ok(Box::new(SimpleCardStrategy::new(player_name))),
+        );
+        registry
+    }
+
+    /// Registers a strategy, replacing any already registered under the same name.
+    pub fn register(&mut self, name: &'static str, description: &'static str, factory: Factory) {
+        self.entries.retain(|entry| entry.name != name);
+        self.entries.push(Entry {
+            name: name,
+            description: description,
+            factory: factory,
+        });
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|entry| entry.name).collect()
+    }
+
+    pub fn description(&self, name: &str) -> Option<&'static str> {
+        self.entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .map(|entry| entry.description)
+    }
+
+    pub fn create(
+        &self,
+        name: &str,
+        player_name: PlayerName,
+        parameters: &Value,
+    ) -> Result<Box<CardStrategy>> {
+        match self.entries.iter().find(|entry| entry.name == name) {
+            Some(entry) => (entry.factory)(player_name, parameters),
+            None => Err(Error::Game(format!(
+                "Unknown strategy {}, choose from {}",
+                name,
+                self.names().join(", ")
+            ))),
+        }
+    }
+}
+
+/// Reads strategy parameters from a JSON file or, if its name ends in `.toml`, a TOML file.
+pub fn read_parameters<P: AsRef<Path>>(path: P) -> Result<Value> {
+    let path = path.as_ref();
+    let mut string = String::new();
+    File::open(path)?.read_to_string(&mut string)?;
+    if path.extension().map_or(false, |extension| extension == "toml") {
+        Ok(toml::from_str(&string)?)
+    } else {
+        Ok(serde_json::from_str(&string)?)
+    }
+}
+
+/// Parameters of a strategy, or their defaults when none were given.
+pub fn parameters<A: DeserializeOwned + Default>(parameters: &Value) -> Result<A> {
+    match *parameters {
+        Value::Null => Ok(A::default()),
+        _ => Ok(serde_json::from_value(parameters.clone())?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn creates_registered_strategies() {
+        let registry = Registry::standard();
+        for name in registry.names() {
+            let strategy = registry.create(name, PlayerName::new("North"), &Value::Null).unwrap();
+            assert_eq!(&PlayerName::new("North"), strategy.player_name());
+        }
+        assert!(registry.create("clever", PlayerName::new("North"), &Value::Null).is_err());
+    }
+
+    #[test]
+    fn checks_parameters() {
+        let registry = Registry::standard();
+        let player_name = PlayerName::new("North");
+        let samples = serde_json::from_str("{\"samples\": 5}").unwrap();
+        let unknown = serde_json::from_str("{\"samplez\": 5}").unwrap();
+
+        assert!(registry.create("monte-carlo", player_name.clone(), &samples).is_ok());
+        assert!(registry.create("monte-carlo", player_name, &unknown).is_err());
+    }
+
+    #[test]
+    fn replaces_strategies() {
+        let mut registry = Registry::standard();
+        registry.register("defensive", "Plays the first legal card", |player_name, _| {
+            Ok(Box::new(SimpleCardStrategy::new(player_name)))
+        });
+
+        assert_eq!(3, registry.names().len());
+        assert_eq!(Some("Plays the first legal card"), registry.description("defensive"));
+    }
+}
@@ -0,0 +1,227 @@
+use engine;
+use engine::Engine;
+use engine::GameResult;
+use error::Error;
+use error::Result;
+use game_status::PlayerName;
+use strategy::Registry;
+
+use serde_json::Value;
+
+use std::fmt;
+
+/// Plays many seeded games between a set of strategies. Every deal is played four times with
+/// the seats rotated, so each entrant gets to play every hand.
+pub struct Tournament {
+    registry: Registry,
+    entrants: Vec<Entrant>,
+    games: usize,
+    seed: usize,
+}
+
+struct Entrant {
+    player_name: PlayerName,
+    strategy_name: String,
+    round_scores: Summary,
+    game_scores: Summary,
+    moon_shots: u32,
+    moon_shots_against: u32,
+    wins: f64,
+}
+
+#[derive(Default)]
+struct Summary {
+    count: u32,
+    sum: f64,
+    sum_of_squares: f64,
+}
+
+impl Tournament {
+    pub fn new(strategy_names: &[&str], games: usize, seed: usize) -> Result<Tournament> {
+        if strategy_names.is_empty() || strategy_names.len() > engine::PLAYER_COUNT {
+            return Err(Error::Game(
+                format!("Between 1 and {} strategies are needed", engine::PLAYER_COUNT),
+            ));
+        }
+        let registry = Registry::standard();
+        let entrants = strategy_names
+            .iter()
+            .cycle()
+            .take(engine::PLAYER_COUNT)
+            .enumerate()
+            .map(|(index, strategy_name)| {
+                let player_name = PlayerName::new("");
+                registry.create(strategy_name, player_name, &Value::Null).map(|_| {
+                    Entrant {
+                        player_name: PlayerName::new(format!("{}:{}", index + 1, strategy_name)),
+                        strategy_name: (*strategy_name).to_owned(),
+                        round_scores: Summary::default(),
+                        game_scores: Summary::default(),
+                        moon_shots: 0,
+                        moon_shots_against: 0,
+                        wins: 0.0,
+                    }
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Tournament {
+            registry: registry,
+            entrants: entrants,
+            games: games,
+            seed: seed,
+        })
+    }
+
+    pub fn run(&mut self) {
+        for game in 0..self.games {
+            let rotation = game % engine::PLAYER_COUNT;
+            let seed = self.seed + game / engine::PLAYER_COUNT;
+            let strategies = (0..engine::PLAYER_COUNT)
+                .map(|seat| {
+                    let entrant = &self.entrants[(seat + rotation) % engine::PLAYER_COUNT];
+                    let player_name = entrant.player_name.clone();
+                    self.registry
+                        .create(&entrant.strategy_name, player_name, &Value::Null)
+                        .unwrap()
+                })
+                .collect();
+            let result = Engine::new(strategies, seed).play_game();
+            self.record(&result);
+            if (game + 1) % 100 == 0 {
+                info!("Played {} of {} games", game + 1, self.games);
+            }
+        }
+    }
+
+    fn record(&mut self, result: &GameResult) {
+        for round in &result.rounds {
+            let scores = round.scores();
+            debug!("Game {} round {}: {:?}", result.game_id, round.round_id, scores);
+            for entrant in &mut self.entrants {
+                entrant.round_scores.add(scores[&entrant.player_name] as f64);
+                match round.shooter {
+                    Some(ref shooter) if shooter == &entrant.player_name => {
+                        entrant.moon_shots += 1
+                    }
+                    Some(_) => entrant.moon_shots_against += 1,
+                    None => (),
+                }
+            }
+        }
+
+        let scores = result.scores();
+        let best = scores.values().min().cloned().unwrap_or_default();
+        let winners = scores.values().filter(|&&score| score == best).count();
+        for entrant in &mut self.entrants {
+            let score = scores[&entrant.player_name];
+            entrant.game_scores.add(score as f64);
+            if score == best {
+                entrant.wins += 1.0 / winners as f64;
+            }
+        }
+    }
+}
+
+impl fmt::Display for Tournament {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} games, {} rounds each, first seed {}",
+            self.games,
+            engine::ROUND_COUNT,
+            self.seed
+        )?;
+        writeln!(
+            f,
+            "{: <16} {: >10} {: >8} {: >10} {: >6} {: >8} {: >8}",
+            "Strategy",
+            "Points",
+            "\u{b1}95%",
+            "Per game",
+            "Moons",
+            "Against",
+            "Win rate"
+        )?;
+        for entrant in &self.entrants {
+            writeln!(
+                f,
+                "{: <16} {: >10.3} {: >8.3} {: >10.3} {: >6} {: >8} {: >7.1}%",
+                format!("{}", entrant.player_name),
+                entrant.round_scores.mean(),
+                entrant.round_scores.confidence_interval(),
+                entrant.game_scores.mean(),
+                entrant.moon_shots,
+                entrant.moon_shots_against,
+                100.0 * entrant.wins / self.games.max(1) as f64
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Summary {
+    fn add(&mut self, value: f64) {
+        self.count += 1;
+        self.sum += value;
+        self.sum_of_squares += value * value;
+    }
+
+    fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.sum / self.count as f64
+        }
+    }
+
+    fn standard_deviation(&self) -> f64 {
+        if self.count < 2 {
+            0.0
+        } else {
+            let count = self.count as f64;
+            let variance = (self.sum_of_squares - self.sum * self.sum / count) / (count - 1.0);
+            variance.max(0.0).sqrt()
+        }
+    }
+
+    /// Half-width of the 95% confidence interval of the mean.
+    fn confidence_interval(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            1.96 * self.standard_deviation() / (self.count as f64).sqrt()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary_statistics() {
+        let mut summary = Summary::default();
+        for value in &[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0] {
+            summary.add(*value);
+        }
+        assert_eq!(5.0, summary.mean());
+        assert!((summary.standard_deviation() - 2.138).abs() < 0.001);
+    }
+
+    #[test]
+    fn every_entrant_plays_every_game() {
+        let mut tournament = Tournament::new(&["defensive", "simple"], 4, 1).unwrap();
+        tournament.run();
+
+        for entrant in &tournament.entrants {
+            assert_eq!(4 * engine::ROUND_COUNT, entrant.round_scores.count);
+        }
+        let wins = tournament.entrants.iter().map(|entrant| entrant.wins).sum::<f64>();
+        assert!((wins - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unknown_strategy() {
+        assert!(Tournament::new(&["clever"], 4, 1).is_err());
+    }
+}